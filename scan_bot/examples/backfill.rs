@@ -0,0 +1,62 @@
+// backfill the trades of a mint, the cursor is kept in a file so a rerun only
+// fetches newer trades
+
+use std::{env, fs};
+
+use anyhow::Result;
+use scan_bot::sol_client::{
+    backfill::{BackfillCursor, DEFAULT_BACKFILL_REQUESTS_PER_SECOND},
+    client::SolanaMonitor,
+    event::{PumpEvent, TxEvent},
+};
+use tokio::sync::mpsc;
+use tracing::info;
+use utils::log::init_tracing;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv()?;
+    init_tracing();
+
+    let wss = env::var("WSS_SOLANA_URL")?;
+    let rpc = env::var("RPC_SOLANA_URL")?;
+    let mint = env::var("MINT")?;
+    let cursor_path = format!("backfill-{}.json", mint);
+
+    let mut cursor: BackfillCursor = fs::read_to_string(&cursor_path)
+        .map(|s| serde_json::from_str(&s))
+        .unwrap_or(Ok(BackfillCursor::default()))?;
+
+    let (sender, mut receiver) = mpsc::channel::<TxEvent>(1000);
+    let printer = tokio::spawn(async move {
+        while let Some(TxEvent { context, event }) = receiver.recv().await {
+            if let PumpEvent::Trade(trade) = event {
+                info!(
+                    "slot: {}, signature: {}, buy: {}, sol: {}, token: {}",
+                    context.slot,
+                    context.signature,
+                    trade.data.is_buy,
+                    trade.data.sol_amount,
+                    trade.data.token_amount
+                );
+            }
+        }
+    });
+
+    let sm = SolanaMonitor::new(&wss, &rpc);
+    let result = sm
+        .backfill_trades(
+            &mint,
+            &mut cursor,
+            DEFAULT_BACKFILL_REQUESTS_PER_SECOND,
+            &sender,
+        )
+        .await;
+    // saved even after an error, the next run resumes from here
+    fs::write(&cursor_path, serde_json::to_string(&cursor)?)?;
+    drop(sender);
+    printer.await?;
+
+    info!("backfilled {} trades, cursor: {:?}", result?, cursor);
+    Ok(())
+}
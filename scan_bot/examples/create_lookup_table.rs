@@ -0,0 +1,27 @@
+// create a lookup table holding the static pump accounts, then put its address
+// into send_config.lookup_tables
+
+use std::env;
+
+use anyhow::Result;
+use scan_bot::sol_client::client::SolanaMonitor;
+use solana_sdk::signature::Keypair;
+use tracing::info;
+use utils::log::init_tracing;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv()?;
+    init_tracing();
+
+    let wss = env::var("WSS_SOLANA_URL")?;
+    let rpc = env::var("RPC_SOLANA_URL")?;
+    let private_key = env::var("PRIVATE_KEY")?;
+    let keypair = Keypair::from_base58_string(&private_key);
+
+    let sm = SolanaMonitor::new(&wss, &rpc);
+    let table = sm.create_lookup_table(&keypair).await?;
+    info!("lookup table: {}", table);
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+// watch live bonding curve state of a mint
+
+use std::env;
+
+use anyhow::Result;
+use scan_bot::sol_client::client::SolanaMonitor;
+use tracing::info;
+use utils::log::init_tracing;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv()?;
+    init_tracing();
+
+    let wss = env::var("WSS_SOLANA_URL")?;
+    let rpc = env::var("RPC_SOLANA_URL")?;
+    let mint = env::var("MINT")?;
+
+    let sm = SolanaMonitor::new(&wss, &rpc);
+    let watcher = sm.curve_watcher();
+    let mut updates = watcher.subscribe();
+    watcher.watch(&mint).await?;
+
+    // receive updates
+    while let Ok(update) = updates.recv().await {
+        info!(
+            "slot: {}, price: {}, complete: {}",
+            update.slot,
+            update.price(),
+            update.account.complete
+        );
+        if update.account.complete {
+            watcher.unwatch(&update.mint).await;
+            break;
+        }
+    }
+    Ok(())
+}
@@ -7,7 +7,7 @@ use scan_bot::{
     sol_client::client::SolanaMonitor,
     strategies::{MonitorRule, MonitorRuleType},
 };
-use solana_client::rpc_response::RpcLogsResponse;
+use solana_client::rpc_response::{Response, RpcLogsResponse};
 use tracing::info;
 use utils::log::init_tracing;
 
@@ -22,14 +22,12 @@ async fn main() -> Result<()> {
     let wss = env::var("WSS_SOLANA_URL")?;
     let rpc = env::var("RPC_SOLANA_URL")?;
 
-    let (sender, mut receiver) = tokio::sync::mpsc::channel::<RpcLogsResponse>(1000);
+    let (sender, mut receiver) = tokio::sync::mpsc::channel::<Response<RpcLogsResponse>>(1000);
 
     // start monitoring in a new task
     tokio::spawn(async move {
         let sm = SolanaMonitor::new(&wss, &rpc);
-        sm.start_log_subscribe(&mr.address, sender, None)
-            .await
-            .unwrap();
+        sm.start_log_subscribe(&mr.address, sender).await;
     });
 
     // receive logs
@@ -27,9 +27,7 @@ async fn main() -> Result<()> {
     // start monitoring in a new task
     tokio::spawn(async move {
         let sm = SolanaMonitor::new(&wss, &rpc);
-        sm.start_program_subscribe(&mr.address, sender)
-            .await
-            .unwrap();
+        sm.start_program_subscribe(&mr.address, sender).await;
     });
 
     // receive logs
@@ -1,7 +1,10 @@
 use std::{env, sync::Arc};
 
 use anyhow::Result;
-use scan_bot::{jito::tip_percentile::tip_stream, swap::Swap};
+use scan_bot::{
+    jito::{tip_accounts::tip_accounts_refresher, tip_percentile::spawn_tip_stream},
+    swap::Swap,
+};
 use solana_sdk::signature::Keypair;
 use tokio::time::Instant;
 use tracing::{error, info};
@@ -19,31 +22,34 @@ async fn main() -> Result<()> {
     let swap = Swap::new(Arc::new(keypair));
 
     // spawn a task to listen to the tip stream
-    tokio::spawn(async move { tip_stream().await });
+    spawn_tip_stream();
+    // and keep the tip accounts cached
+    tokio::spawn(tip_accounts_refresher());
 
     let now = Instant::now();
-    // buy
-    if let Err(e) = swap
-        .swap(true, coin_address, 100_000_000_000, 5_000_000, Some(true))
+    // buy with 0.005 sol, slippage from jito_config
+    let result = swap
+        .buy_with_sol(coin_address, 5_000_000)
         .await
-    {
-        error!("Failed to swap: {}", e);
-        return Err(e);
-    }
-    info!("buy ok Time elapsed: {:?}", now.elapsed());
+        .inspect_err(|e| error!("Failed to swap: {}", e))?;
+    info!(
+        "buy sent: {:?}, Time elapsed: {:?}",
+        result.signatures,
+        now.elapsed()
+    );
+    let status = result.landing.wait().await;
+    info!("buy {:?}, Time elapsed: {:?}", status, now.elapsed());
 
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 
     // sell
-    if let Err(e) = swap
-        .swap(false, coin_address, 100_000_000_000, 1_000_000, None)
+    let result = swap
+        .sell_tokens(coin_address, 100_000_000_000)
         .await
-    {
-        error!("Failed to swap: {}", e);
-        return Err(e);
-    }
+        .inspect_err(|e| error!("Failed to swap: {}", e))?;
+    let status = result.landing.wait().await;
 
-    info!("sell Time elapsed: {:?}", now.elapsed());
+    info!("sell {:?}, Time elapsed: {:?}", status, now.elapsed());
 
     Ok(())
 }
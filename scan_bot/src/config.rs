@@ -1,20 +1,30 @@
 use anyhow::Result;
 use serde::Deserialize;
-use std::{env, str::FromStr, sync::Arc};
+use std::{env, str::FromStr, sync::Arc, time::Duration};
 use tokio::{fs, sync::OnceCell};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use crate::{
     jito::JITOConfig,
+    sol_client::{
+        priority_fee::SendConfig, rpc_pool::DEFAULT_RPC_TIMEOUT, source::EventSourceKind,
+    },
     strategies::{scan_dealer::ScanDealerConfig, MonitorRule},
 };
 
 #[derive(Clone, Debug, Validate, Deserialize)]
+#[validate(schema(function = "validate_endpoints"))]
 pub struct Config {
     #[validate(length(min = 1))]
     pub solana_rpc_url: String, // solana rpc url
     #[validate(length(min = 1))]
     pub solana_wss_url: String, // solana wss url
+    #[serde(default)]
+    pub solana_rpc_urls: Vec<String>, // backup rpc providers, failed over to in order
+    #[serde(default)]
+    pub solana_wss_urls: Vec<String>, // more wss providers, log subscriptions race on all
+    #[serde(default = "default_rpc_timeout_ms")]
+    pub rpc_timeout_ms: u64, // per rpc call, then the next provider is tried
     #[validate(length(min = 1))]
     pub private_key: String, // private key
 
@@ -23,6 +33,53 @@ pub struct Config {
     pub monitors: Vec<MonitorRule>, // monitor rules
 
     pub jito_config: JITOConfig, // jito config
+
+    #[serde(default)]
+    pub send_config: SendConfig, // how transactions are sent
+
+    #[serde(default)]
+    pub event_source: EventSourceKind, // where monitors get pump events from
+}
+
+fn default_rpc_timeout_ms() -> u64 {
+    DEFAULT_RPC_TIMEOUT.as_millis() as u64
+}
+
+impl Config {
+    /// Rpc providers, `solana_rpc_url` first.
+    pub fn rpc_urls(&self) -> Vec<String> {
+        endpoints(&self.solana_rpc_url, &self.solana_rpc_urls)
+    }
+
+    /// Websocket providers, `solana_wss_url` first.
+    pub fn wss_urls(&self) -> Vec<String> {
+        endpoints(&self.solana_wss_url, &self.solana_wss_urls)
+    }
+
+    pub fn rpc_timeout(&self) -> Duration {
+        Duration::from_millis(self.rpc_timeout_ms)
+    }
+}
+
+// the monitor needs at least one provider of each kind
+fn validate_endpoints(config: &Config) -> Result<(), ValidationError> {
+    if config.rpc_urls().is_empty() {
+        return Err(ValidationError::new("no_rpc_provider"));
+    }
+    if config.wss_urls().is_empty() {
+        return Err(ValidationError::new("no_wss_provider"));
+    }
+    Ok(())
+}
+
+fn endpoints(first: &str, rest: &[String]) -> Vec<String> {
+    let mut urls: Vec<String> = vec![];
+    for url in std::iter::once(first).chain(rest.iter().map(String::as_str)) {
+        if !url.is_empty() && !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
 }
 
 impl FromStr for Config {
@@ -40,13 +97,13 @@ pub async fn get_global_config() -> &'static Arc<Config> {
 
     GLOBAL_CONFIG
         .get_or_init(|| async {
-            Arc::new(
-                fs::read_to_string(config_url)
-                    .await
-                    .expect("Failed to read config file")
-                    .parse::<Config>()
-                    .expect("Failed to parse config"),
-            )
+            let config = fs::read_to_string(config_url)
+                .await
+                .expect("Failed to read config file")
+                .parse::<Config>()
+                .expect("Failed to parse config");
+            config.validate().expect("Invalid config");
+            Arc::new(config)
         })
         .await
 }
@@ -1,172 +1,429 @@
-use std::time::Duration;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use super::JITO;
-use anyhow::{anyhow, Result};
-use tokio::time::sleep;
-use tracing::{error, info};
+use super::{
+    types::{BundleStatus, InflightBundleStatus, InflightStatus},
+    JITO,
+};
+use crate::sol_client::landing::{decode_bundle_error, LandingStatus};
+use anyhow::Result;
+use solana_transaction_status::TransactionConfirmationStatus;
+use tokio::{
+    sync::{broadcast, mpsc, OnceCell},
+    time::{sleep, Instant},
+};
+use tracing::{debug, error, info, warn};
+
+// getInflightBundleStatuses and getBundleStatuses take at most 5 ids
+const MAX_IDS_PER_REQUEST: usize = 5;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// a bundle not landed by then is given up as expired
+const MAX_TRACK_DURATION: Duration = Duration::from_secs(60);
+// a landed bundle gets this much longer to confirm, about 150 blocks of 400ms
+const MAX_CONFIRM_DURATION: Duration = Duration::from_secs(60);
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+static BUNDLE_TRACKER: OnceCell<Arc<BundleTracker>> = OnceCell::const_new();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleState {
+    Pending,
+    Landed { slot: u64 }, // landed, waiting for confirmation
+    Done(LandingStatus),  // final, the bundle is no longer tracked
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleEvent {
+    pub bundle_id: String,
+    pub state: BundleState,
+}
 
 #[derive(Debug)]
-struct BundleStatus {
-    confirmation_status: Option<String>,
-    err: Option<serde_json::Value>,
-    transactions: Option<Vec<String>>,
+struct TrackedBundle {
+    signature: String, // first transaction, for statuses that don't carry it
+    state: BundleState,
+    added_at: Instant,
 }
 
-impl JITO {
-    pub async fn check_bundle_status(&self, bundle_uuid: &str) -> Result<()> {
-        let max_retries = 10;
-        let retry_delay = Duration::from_secs(2);
-
-        for attempt in 1..=max_retries {
-            info!(
-                "Checking final bundle status (attempt {}/{})",
-                attempt, max_retries
-            );
-            let status_response = self
-                .client
-                .get_in_flight_bundle_statuses(vec![bundle_uuid.to_string()])
-                .await?;
-
-            if let Some(result) = status_response.get("result") {
-                if let Some(value) = result.get("value") {
-                    if let Some(statuses) = value.as_array() {
-                        if let Some(bundle_status) = statuses.get(0) {
-                            if let Some(status) = bundle_status.get("status") {
-                                match status.as_str() {
-                                    Some("Landed") => {
-                                        info!("Bundle landed on-chain. Checking final status...");
-                                        return self.check_final_bundle_status(bundle_uuid).await;
-                                    }
-                                    Some("Pending") => {
-                                        info!("Bundle is pending. Waiting...");
-                                    }
-                                    Some(status) => {
-                                        info!("Unexpected bundle status: {}. Waiting...", status);
-                                    }
-                                    None => {
-                                        info!("Unable to parse bundle status. Waiting...");
-                                    }
-                                }
-                            } else {
-                                info!("Status field not found in bundle status. Waiting...");
-                            }
-                        } else {
-                            info!("Bundle status not found. Waiting...");
+impl TrackedBundle {
+    // new state from an in-flight status, None when unchanged
+    fn on_inflight(&self, status: &InflightBundleStatus) -> Option<BundleState> {
+        let state = match status.status {
+            InflightStatus::Pending => BundleState::Pending,
+            InflightStatus::Landed => BundleState::Landed {
+                slot: status.landed_slot.unwrap_or_default(),
+            },
+            InflightStatus::Failed => BundleState::Done(LandingStatus::Failed {
+                signature: self.signature.clone(),
+                error: "bundle failed".to_string(),
+            }),
+            InflightStatus::Invalid => BundleState::Done(LandingStatus::Expired),
+            InflightStatus::Unknown => return None,
+        };
+        (state != self.state).then_some(state)
+    }
+
+    // final state once the landed bundle is confirmed
+    fn on_final(&self, status: &BundleStatus) -> Option<BundleState> {
+        match status.confirmation_status {
+            Some(TransactionConfirmationStatus::Confirmed)
+            | Some(TransactionConfirmationStatus::Finalized) => {
+                print_transaction_url(status);
+                let status = match landing_status(status) {
+                    LandingStatus::Failed { signature, error } if signature.is_empty() => {
+                        LandingStatus::Failed {
+                            signature: self.signature.clone(),
+                            error,
                         }
-                    } else {
-                        info!("Unexpected value format. Waiting...");
                     }
-                } else {
-                    info!("Value field not found in result. Waiting...");
-                }
-            } else if let Some(error) = status_response.get("error") {
-                info!("Error checking bundle status: {:?}", error);
-            } else {
-                info!("Unexpected response format. Waiting...");
+                    status => status,
+                };
+                Some(BundleState::Done(status))
             }
+            _ => None,
+        }
+    }
 
-            if attempt < max_retries {
-                sleep(retry_delay).await;
+    // only a bundle that never landed expires; a landed one that is still
+    // unconfirmed at its deadline is reported landed, it may have executed
+    fn expired(&self, now: Instant) -> Option<BundleState> {
+        let tracked_for = now.duration_since(self.added_at);
+        match self.state {
+            BundleState::Pending if tracked_for > MAX_TRACK_DURATION => {
+                Some(BundleState::Done(LandingStatus::Expired))
+            }
+            BundleState::Landed { slot }
+                if tracked_for > MAX_TRACK_DURATION + MAX_CONFIRM_DURATION =>
+            {
+                warn!(
+                    "bundle landed in slot {} but was not confirmed in time",
+                    slot
+                );
+                Some(BundleState::Done(LandingStatus::Landed {
+                    signature: self.signature.clone(),
+                    slot,
+                }))
             }
+            _ => None,
         }
-        Ok(())
-    }
-    async fn check_final_bundle_status(&self, bundle_uuid: &str) -> Result<()> {
-        let max_retries = 10;
-        let retry_delay = Duration::from_secs(2);
-
-        for attempt in 1..=max_retries {
-            info!(
-                "Checking final bundle status (attempt {}/{})",
-                attempt, max_retries
-            );
-
-            let status_response = self
-                .client
-                .get_bundle_statuses(vec![bundle_uuid.to_string()])
-                .await?;
-            let bundle_status = get_bundle_status(&status_response)?;
-
-            match bundle_status.confirmation_status.as_deref() {
-                Some("confirmed") => {
-                    info!("Bundle confirmed on-chain. Waiting for finalization...");
-                    check_transaction_error(&bundle_status)?;
-                }
-                Some("finalized") => {
-                    info!("Bundle finalized on-chain successfully!");
-                    check_transaction_error(&bundle_status)?;
-                    print_transaction_url(&bundle_status);
-                    return Ok(());
+    }
+}
+
+/// Tracks every sent bundle in one background task, polling statuses in
+/// batches and broadcasting each state change.
+pub struct BundleTracker {
+    new_bundles: mpsc::UnboundedSender<(String, String)>,
+    events: broadcast::Sender<BundleEvent>,
+}
+
+pub async fn get_bundle_tracker() -> &'static Arc<BundleTracker> {
+    BUNDLE_TRACKER
+        .get_or_init(|| async {
+            let (new_bundles, receiver) = mpsc::unbounded_channel();
+            let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+            let jito = JITO::default_client().await;
+            tokio::spawn(run_tracker(jito, receiver, events.clone()));
+            Arc::new(BundleTracker {
+                new_bundles,
+                events,
+            })
+        })
+        .await
+}
+
+impl BundleTracker {
+    /// Start tracking `bundle_id`, `signature` is its first transaction.
+    pub fn track(&self, bundle_id: &str, signature: &str) {
+        if self
+            .new_bundles
+            .send((bundle_id.to_string(), signature.to_string()))
+            .is_err()
+        {
+            error!("bundle tracker stopped, not tracking {}", bundle_id);
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BundleEvent> {
+        self.events.subscribe()
+    }
+
+    /// Track `bundle_id` and wait for its final status.
+    pub async fn wait(&self, bundle_id: &str, signature: &str) -> LandingStatus {
+        let mut events = self.subscribe();
+        self.track(bundle_id, signature);
+        loop {
+            match events.recv().await {
+                Ok(BundleEvent {
+                    bundle_id: id,
+                    state: BundleState::Done(status),
+                }) if id == bundle_id => return status,
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("bundle tracker: skipped {} events", n);
                 }
-                Some(status) => {
-                    info!(
-                        "Unexpected final bundle status: {}. Continuing to poll...",
-                        status
-                    );
+                Err(broadcast::error::RecvError::Closed) => {
+                    return LandingStatus::Failed {
+                        signature: signature.to_string(),
+                        error: "bundle tracker stopped".to_string(),
+                    }
                 }
-                None => {
-                    info!("Unable to parse final bundle status. Continuing to poll...");
+            }
+        }
+    }
+}
+
+async fn run_tracker(
+    jito: JITO,
+    mut new_bundles: mpsc::UnboundedReceiver<(String, String)>,
+    events: broadcast::Sender<BundleEvent>,
+) {
+    let mut bundles: HashMap<String, TrackedBundle> = HashMap::new();
+    loop {
+        if bundles.is_empty() {
+            // idle until something is sent
+            match new_bundles.recv().await {
+                Some((bundle_id, signature)) => add_bundle(&mut bundles, bundle_id, signature),
+                None => return,
+            }
+        }
+        while let Ok((bundle_id, signature)) = new_bundles.try_recv() {
+            add_bundle(&mut bundles, bundle_id, signature);
+        }
+
+        let mut changes = poll_inflight(&jito, &bundles).await;
+        changes.extend(poll_final(&jito, &bundles).await);
+        let now = Instant::now();
+        for (bundle_id, bundle) in &bundles {
+            if !changes.iter().any(|(id, _)| id == bundle_id) {
+                if let Some(state) = bundle.expired(now) {
+                    changes.push((bundle_id.clone(), state));
                 }
             }
+        }
 
-            if attempt < max_retries {
-                sleep(retry_delay).await;
+        for (bundle_id, state) in changes {
+            info!("bundle {}: {:?}", bundle_id, state);
+            if matches!(state, BundleState::Done(_)) {
+                bundles.remove(&bundle_id);
+            } else if let Some(bundle) = bundles.get_mut(&bundle_id) {
+                bundle.state = state.clone();
             }
+            // no subscribers is fine
+            let _ = events.send(BundleEvent { bundle_id, state });
         }
 
-        Err(anyhow!(
-            "Failed to get finalized status after {} attempts",
-            max_retries
-        ))
+        sleep(POLL_INTERVAL).await;
     }
 }
 
-fn get_bundle_status(status_response: &serde_json::Value) -> Result<BundleStatus> {
-    status_response
-        .get("result")
-        .and_then(|result| result.get("value"))
-        .and_then(|value| value.as_array())
-        .and_then(|statuses| statuses.get(0))
-        .ok_or_else(|| anyhow!("Failed to parse bundle status"))
-        .map(|bundle_status| BundleStatus {
-            confirmation_status: bundle_status
-                .get("confirmation_status")
-                .and_then(|s| s.as_str())
-                .map(String::from),
-            err: bundle_status.get("err").cloned(),
-            transactions: bundle_status
-                .get("transactions")
-                .and_then(|t| t.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect()
-                }),
-        })
+fn add_bundle(bundles: &mut HashMap<String, TrackedBundle>, bundle_id: String, signature: String) {
+    debug!("bundle tracker: tracking {}", bundle_id);
+    bundles.entry(bundle_id).or_insert(TrackedBundle {
+        signature,
+        state: BundleState::Pending,
+        added_at: Instant::now(),
+    });
 }
 
-fn check_transaction_error(bundle_status: &BundleStatus) -> Result<()> {
-    if let Some(err) = &bundle_status.err {
-        if err["Ok"].is_null() {
+// in-flight statuses of bundles not known to have landed yet
+async fn poll_inflight(
+    jito: &JITO,
+    bundles: &HashMap<String, TrackedBundle>,
+) -> Vec<(String, BundleState)> {
+    let ids: Vec<String> = bundles
+        .iter()
+        .filter(|(_, b)| b.state == BundleState::Pending)
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    let mut changes = vec![];
+    for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+        match jito
+            .client
+            .get_in_flight_bundle_statuses(chunk.to_vec())
+            .await
+        {
+            Ok(response) => {
+                for status in response.value.iter().flatten() {
+                    if let Some(state) = bundles
+                        .get(&status.bundle_id)
+                        .and_then(|b| b.on_inflight(status))
+                    {
+                        changes.push((status.bundle_id.clone(), state));
+                    }
+                }
+            }
+            // sends have the rate limit, try again next round
+            Err(e) if e.is_rate_limited() => break,
+            Err(e) => warn!("Error checking in-flight bundle statuses: {}", e),
+        }
+    }
+    changes
+}
+
+// confirmation of landed bundles
+async fn poll_final(
+    jito: &JITO,
+    bundles: &HashMap<String, TrackedBundle>,
+) -> Vec<(String, BundleState)> {
+    let ids: Vec<String> = bundles
+        .iter()
+        .filter(|(_, b)| matches!(b.state, BundleState::Landed { .. }))
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    let mut changes = vec![];
+    for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+        match jito.client.get_bundle_statuses(chunk.to_vec()).await {
+            Ok(response) => {
+                for status in response.value.iter().flatten() {
+                    if let Some(state) = bundles
+                        .get(&status.bundle_id)
+                        .and_then(|b| b.on_final(status))
+                    {
+                        changes.push((status.bundle_id.clone(), state));
+                    }
+                }
+            }
+            // sends have the rate limit, try again next round
+            Err(e) if e.is_rate_limited() => break,
+            Err(e) => warn!("Error checking bundle statuses: {}", e),
+        }
+    }
+    changes
+}
+
+impl JITO {
+    /// Wait until a bundle lands, fails or expires, via the shared tracker.
+    /// `signature` is the bundle's first transaction.
+    pub async fn check_bundle_status(
+        &self,
+        bundle_uuid: &str,
+        signature: &str,
+    ) -> Result<LandingStatus> {
+        Ok(get_bundle_tracker()
+            .await
+            .wait(bundle_uuid, signature)
+            .await)
+    }
+}
+
+fn landing_status(bundle_status: &BundleStatus) -> LandingStatus {
+    let signature = bundle_status
+        .transactions
+        .first()
+        .cloned()
+        .unwrap_or_default();
+
+    match bundle_status.err.as_ref().and_then(decode_bundle_error) {
+        Some(error) => {
+            error!("Transaction encountered an error: {}", error);
+            LandingStatus::Failed { signature, error }
+        }
+        None => {
             info!("Transaction executed without errors.");
-            Ok(())
-        } else {
-            error!("Transaction encountered an error: {:?}", err);
-            Err(anyhow!("Transaction encountered an error"))
+            LandingStatus::Landed {
+                signature,
+                slot: bundle_status.slot,
+            }
         }
-    } else {
-        Ok(())
     }
 }
 
 fn print_transaction_url(bundle_status: &BundleStatus) {
-    if let Some(transactions) = &bundle_status.transactions {
-        if let Some(tx_id) = transactions.first() {
-            info!("Transaction URL: https://solscan.io/tx/{}", tx_id);
-        } else {
-            info!("Unable to extract transaction ID.");
-        }
+    if let Some(tx_id) = bundle_status.transactions.first() {
+        info!("Transaction URL: https://solscan.io/tx/{}", tx_id);
     } else {
         info!("No transactions found in the bundle status.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tracked(state: BundleState) -> TrackedBundle {
+        TrackedBundle {
+            signature: "sig1".to_string(),
+            state,
+            added_at: Instant::now(),
+        }
+    }
+
+    fn inflight(status: InflightStatus, landed_slot: Option<u64>) -> InflightBundleStatus {
+        InflightBundleStatus {
+            bundle_id: "b1".to_string(),
+            status,
+            landed_slot,
+        }
+    }
+
+    #[test]
+    fn test_on_inflight() {
+        let bundle = tracked(BundleState::Pending);
+        assert_eq!(
+            bundle.on_inflight(&inflight(InflightStatus::Pending, None)),
+            None
+        );
+        assert_eq!(
+            bundle.on_inflight(&inflight(InflightStatus::Landed, Some(10))),
+            Some(BundleState::Landed { slot: 10 })
+        );
+        assert_eq!(
+            bundle.on_inflight(&inflight(InflightStatus::Failed, None)),
+            Some(BundleState::Done(LandingStatus::Failed {
+                signature: "sig1".to_string(),
+                error: "bundle failed".to_string(),
+            }))
+        );
+        assert_eq!(
+            bundle.on_inflight(&inflight(InflightStatus::Invalid, None)),
+            Some(BundleState::Done(LandingStatus::Expired))
+        );
+        assert_eq!(
+            bundle.on_inflight(&inflight(InflightStatus::Unknown, None)),
+            None
+        );
+        assert_eq!(bundle.expired(Instant::now()), None);
+        assert_eq!(
+            bundle.expired(Instant::now() + MAX_TRACK_DURATION * 2),
+            Some(BundleState::Done(LandingStatus::Expired))
+        );
+    }
+
+    #[test]
+    fn test_landed_never_expires() {
+        let bundle = tracked(BundleState::Landed { slot: 10 });
+        let now = Instant::now();
+        assert_eq!(bundle.expired(now + MAX_TRACK_DURATION * 3 / 2), None);
+        assert_eq!(
+            bundle.expired(now + (MAX_TRACK_DURATION + MAX_CONFIRM_DURATION) * 2),
+            Some(BundleState::Done(LandingStatus::Landed {
+                signature: "sig1".to_string(),
+                slot: 10,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_on_final() {
+        let bundle = tracked(BundleState::Landed { slot: 10 });
+        let mut status = BundleStatus {
+            bundle_id: "b1".to_string(),
+            transactions: vec!["sig1".to_string()],
+            slot: 10,
+            confirmation_status: Some(TransactionConfirmationStatus::Processed),
+            err: Some(json!({"Ok": null})),
+        };
+        assert_eq!(bundle.on_final(&status), None);
+
+        status.confirmation_status = Some(TransactionConfirmationStatus::Confirmed);
+        assert_eq!(
+            bundle.on_final(&status),
+            Some(BundleState::Done(LandingStatus::Landed {
+                signature: "sig1".to_string(),
+                slot: 10,
+            }))
+        );
+    }
+}
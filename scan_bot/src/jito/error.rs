@@ -0,0 +1,87 @@
+use std::{fmt, time::Duration};
+
+use serde_json::Value;
+
+// block engine json-rpc code for "network congested", sent with http 429
+pub const RATE_LIMITED_CODE: i64 = -32097;
+
+#[derive(Debug)]
+pub enum JitoError {
+    // http 429
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    // http 5xx
+    Server {
+        status: u16,
+        body: String,
+    },
+    // other non-success status
+    Http {
+        status: u16,
+        body: String,
+    },
+    // json-rpc error object, e.g. a rejected bundle
+    Rpc {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    InvalidRequest(String),  // rejected before sending
+    InvalidResponse(String), // body is not the expected json-rpc response
+    Request(reqwest::Error), // connect / timeout / body read
+}
+
+impl JitoError {
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(
+            self,
+            JitoError::RateLimited { .. }
+                | JitoError::Rpc {
+                    code: RATE_LIMITED_CODE,
+                    ..
+                }
+        )
+    }
+
+    /// Worth retrying the same request later.
+    pub fn is_retryable(&self) -> bool {
+        self.is_rate_limited() || matches!(self, JitoError::Server { .. } | JitoError::Request(_))
+    }
+}
+
+impl fmt::Display for JitoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JitoError::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(f, "jito: rate limited, retry after {:?}", retry_after),
+            JitoError::RateLimited { retry_after: None } => write!(f, "jito: rate limited"),
+            JitoError::Server { status, body } => {
+                write!(f, "jito: server error {}: {}", status, body)
+            }
+            JitoError::Http { status, body } => write!(f, "jito: http error {}: {}", status, body),
+            JitoError::Rpc { code, message, .. } => {
+                write!(f, "jito: rpc error {}: {}", code, message)
+            }
+            JitoError::InvalidRequest(e) => write!(f, "jito: invalid request: {}", e),
+            JitoError::InvalidResponse(e) => write!(f, "jito: invalid response: {}", e),
+            JitoError::Request(e) => write!(f, "jito: request error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JitoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JitoError::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for JitoError {
+    fn from(e: reqwest::Error) -> Self {
+        JitoError::Request(e)
+    }
+}
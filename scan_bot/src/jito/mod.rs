@@ -1,72 +1,164 @@
-use std::str::FromStr;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use rate_limit::get_rate_limiter;
 use sdk::JitoJsonRpcSDK;
 use serde::Deserialize;
-use serde_json::Value;
 use solana_sdk::pubkey::Pubkey;
-use tip_percentile::get_tip_percentile;
-use tracing::error;
+use tip_accounts::random_tip_account;
+use tip_percentile::{fresh_tip_data, get_tip_percentile};
+use tip_policy::{default_ceiling, TipContext, TipPolicyConfig, TipPolicyKind, TipRequest};
+use tracing::{debug, error};
 
 use crate::config::get_global_config;
 
+pub const DEFAULT_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf/api/v1";
+
 pub mod bundle_status;
+pub mod error;
+pub mod rate_limit;
+pub mod region;
 pub mod sdk;
+pub mod simulation;
+pub mod tip_accounts;
 pub mod tip_percentile;
+pub mod tip_policy;
+pub mod types;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct JITOConfig {
-    pub tips_percentile: u32,
+    #[serde(default = "default_tips_percentile")]
+    pub tips_percentile: u32, // percentile policy used when `tip_policy` is not set
     pub tip_stream_url: String,
+    #[serde(default)]
     pub jito_sdk_url: String, // https://mainnet.block-engine.jito.wtf/api/v1
-    pub extra_tip: f64,       // 额外的小费 0.0001
-    pub slippage: u64,        // 交易滑点 30表示30%
+    #[serde(default)]
+    pub jito_sdk_urls: Vec<String>, // regional block engines, bundles are sent to all
+    pub extra_tip: f64, // 额外的小费 0.0001
+    pub slippage: u64,  // 交易滑点 30表示30%
+    #[serde(default = "default_tip_max_age_secs")]
+    pub tip_max_age_secs: u64, // tip stream data older than this is stale
+    #[serde(default)]
+    pub default_tip: Option<f64>, // sol, used while tip data is missing or stale
+    #[serde(default)]
+    pub tip_policy: Option<TipPolicyConfig>, // how tips are priced
+    #[serde(default)]
+    pub simulate_bundle: bool, // simulateBundle before sending, abort on failure
+    #[serde(default)]
+    pub simulate_bundle_url: String, // jito-solana rpc, solana_rpc_url when empty
+    #[serde(default)]
+    pub uuid: Option<String>, // auth uuid for higher rate limits
+    #[serde(default = "default_requests_per_second")]
+    pub requests_per_second: f64, // allowed rate per block engine region
+    #[serde(default = "default_rate_limit_max_wait_ms")]
+    pub rate_limit_max_wait_ms: u64, // queue this long for the rate limit, then shed
+}
+
+fn default_requests_per_second() -> f64 {
+    1.0 // default limit without an approved uuid
+}
+
+fn default_rate_limit_max_wait_ms() -> u64 {
+    2_000
+}
+
+fn default_tips_percentile() -> u32 {
+    50
+}
+
+fn default_tip_max_age_secs() -> u64 {
+    60
+}
+
+impl JITOConfig {
+    pub fn tip_policy(&self) -> TipPolicyConfig {
+        self.tip_policy.clone().unwrap_or(TipPolicyConfig {
+            kind: TipPolicyKind::Percentile {
+                percentile: self.tips_percentile as f64,
+            },
+            floor: 0.0,
+            ceiling: default_ceiling(),
+        })
+    }
+
+    /// Block engines to send bundles to, `jito_sdk_url` first.
+    pub fn block_engine_urls(&self) -> Vec<String> {
+        let mut urls = vec![];
+        for url in std::iter::once(&self.jito_sdk_url).chain(&self.jito_sdk_urls) {
+            if !url.is_empty() && !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
 }
 
 pub struct JITO {
-    pub client: JitoJsonRpcSDK,
+    pub client: JitoJsonRpcSDK, // first block engine, used for queries
+    pub region_clients: Vec<Arc<JitoJsonRpcSDK>>, // every block engine, bundles fan out to all
 }
 
 impl JITO {
     pub async fn default_client() -> Self {
         let c = get_global_config().await;
-        let sdk = JitoJsonRpcSDK::new(&c.jito_config.jito_sdk_url, None);
-        Self { client: sdk }
+        let urls = c.jito_config.block_engine_urls();
+        let urls = if urls.is_empty() {
+            error!("jito: no block engine url configured, using mainnet");
+            vec![DEFAULT_BLOCK_ENGINE_URL.to_string()]
+        } else {
+            urls
+        };
+        let mut region_clients = Vec::with_capacity(urls.len());
+        for url in &urls {
+            region_clients.push(Arc::new(Self::region_client(&c.jito_config, url).await));
+        }
+        Self {
+            client: Self::region_client(&c.jito_config, &urls[0]).await,
+            region_clients,
+        }
     }
 
-    pub async fn get_random_tip_account(&self) -> Result<Pubkey> {
-        let account = self.client.get_random_tip_account().await?;
-        Ok(Pubkey::from_str(&account).inspect_err(|err| {
-            error!("jito: failed to parse Pubkey: {:?}", err);
-        })?)
+    // authenticated, sharing the rate limit of its region with every other client
+    async fn region_client(config: &JITOConfig, url: &str) -> JitoJsonRpcSDK {
+        let rate_limiter = get_rate_limiter(
+            url,
+            config.requests_per_second,
+            Duration::from_millis(config.rate_limit_max_wait_ms),
+        )
+        .await;
+        JitoJsonRpcSDK::new(url, config.uuid.clone()).with_rate_limiter(rate_limiter)
     }
-    pub async fn send_bundle(&self, bundle: Option<Value>, uuid: Option<&str>) -> Result<String> {
-        let response = self.client.send_bundle(bundle, uuid).await?;
-        let bundle_uuid = response["result"]
-            .as_str()
-            .ok_or_else(|| anyhow!("Failed to get bundle UUID from response"))?
-            .to_string();
-        Ok(bundle_uuid)
+
+    /// Client for `simulateBundle`, which rpc nodes serve, not block engines.
+    pub async fn simulation_client() -> JitoJsonRpcSDK {
+        let c = get_global_config().await;
+        let url = if c.jito_config.simulate_bundle_url.is_empty() {
+            &c.solana_rpc_url
+        } else {
+            &c.jito_config.simulate_bundle_url
+        };
+        JitoJsonRpcSDK::new(url, None)
+    }
+
+    // from the tip account cache, no round trip once loaded
+    pub async fn get_random_tip_account(&self) -> Result<Pubkey> {
+        random_tip_account(&self.client).await
     }
 }
 
-// unit sol
-pub async fn get_tip_value() -> Result<f64> {
+// unit sol, extra_tip included and still capped by the policy ceiling
+pub async fn get_tip_value(request: TipRequest) -> Result<f64> {
     let c = get_global_config().await;
-    let tip_percent = c.jito_config.tips_percentile;
-
-    let tips = get_tip_percentile().await.read().await.clone();
-
-    if let Some(ref data) = tips {
-        match tip_percent {
-            25 => Ok(data.landed_tips_25th_percentile),
-            50 => Ok(data.landed_tips_50th_percentile),
-            75 => Ok(data.landed_tips_75th_percentile),
-            95 => Ok(data.landed_tips_95th_percentile),
-            99 => Ok(data.landed_tips_99th_percentile),
-            _ => Err(anyhow!("jito: invalid TIP_PERCENTILE value")),
-        }
-    } else {
-        Err(anyhow!("jito: no tip percentile data available"))
-    }
+    let update = get_tip_percentile().await.read().await.clone();
+    let max_age = Duration::from_secs(c.jito_config.tip_max_age_secs);
+    let tips = fresh_tip_data(update.as_ref(), max_age, Instant::now())
+        .inspect_err(|e| debug!("{}", e))
+        .ok();
+
+    let policy = c.jito_config.tip_policy();
+    let tip = policy.tip_value(&TipContext { tips, request }, c.jito_config.default_tip)?;
+    Ok(policy.clamp(tip + c.jito_config.extra_tip))
 }
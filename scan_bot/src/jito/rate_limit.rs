@@ -0,0 +1,185 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{Mutex, OnceCell},
+    time::Instant,
+};
+use tracing::{debug, warn};
+
+use super::error::JitoError;
+
+// block engine limits are per ip per region, so one limiter per url
+type RateLimiters = Mutex<HashMap<String, Arc<RateLimiter>>>;
+
+static RATE_LIMITERS: OnceCell<RateLimiters> = OnceCell::const_new();
+
+/// Shared limiter of the block engine at `url`.
+pub async fn get_rate_limiter(
+    url: &str,
+    requests_per_second: f64,
+    max_wait: Duration,
+) -> Arc<RateLimiter> {
+    let limiters = RATE_LIMITERS
+        .get_or_init(|| async { Mutex::new(HashMap::new()) })
+        .await;
+    limiters
+        .lock()
+        .await
+        .entry(url.to_string())
+        .or_insert_with(|| Arc::new(RateLimiter::new(requests_per_second, max_wait)))
+        .clone()
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,     // tokens per second
+    capacity: f64, // burst size
+    tokens: f64,   // negative while requests are queued
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, now: Instant) {
+        // updated_at is in the future while paused after a 429
+        if now > self.updated_at {
+            let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+            self.updated_at = now;
+        }
+    }
+
+    // how long until a token would be available for a new request
+    fn wait(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        let ready_at = if self.tokens >= 1.0 {
+            self.updated_at
+        } else {
+            self.updated_at + Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        };
+        ready_at.saturating_duration_since(now)
+    }
+
+    fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+
+    // no tokens until `until`
+    fn pause(&mut self, until: Instant) {
+        self.tokens = self.tokens.min(0.0);
+        self.updated_at = self.updated_at.max(until);
+    }
+}
+
+/// Token bucket in front of a block engine. Requests queue for a token up to
+/// `max_wait`, beyond that they are shed with `JitoError::RateLimited`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Mutex<TokenBucket>,
+    max_wait: Duration,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64, max_wait: Duration) -> Self {
+        let rate = requests_per_second.max(0.01);
+        let capacity = rate.max(1.0);
+        Self {
+            bucket: Mutex::new(TokenBucket {
+                rate,
+                capacity,
+                tokens: capacity,
+                updated_at: Instant::now(),
+            }),
+            max_wait,
+        }
+    }
+
+    /// Wait for a token, or fail right away when the queue is too long.
+    pub async fn acquire(&self) -> Result<(), JitoError> {
+        let wait = {
+            let mut bucket = self.bucket.lock().await;
+            let wait = bucket.wait(Instant::now());
+            if wait > self.max_wait {
+                warn!("jito: shedding request, rate limit wait {:?}", wait);
+                return Err(JitoError::RateLimited {
+                    retry_after: Some(wait),
+                });
+            }
+            bucket.take();
+            wait
+        };
+        if !wait.is_zero() {
+            debug!("jito: rate limited, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Take a token only if one is free right now, never queueing. Background
+    /// queries use this so they can't push sends past `max_wait`.
+    pub async fn try_acquire_spare(&self) -> Result<(), JitoError> {
+        let mut bucket = self.bucket.lock().await;
+        let wait = bucket.wait(Instant::now());
+        if !wait.is_zero() {
+            debug!("jito: no spare rate limit, skipping query");
+            return Err(JitoError::RateLimited {
+                retry_after: Some(wait),
+            });
+        }
+        bucket.take();
+        Ok(())
+    }
+
+    /// Back off after a 429, nothing is sent for `retry_after`.
+    pub async fn throttled(&self, retry_after: Duration) {
+        warn!("jito: throttled by block engine, pausing {:?}", retry_after);
+        self.bucket.lock().await.pause(Instant::now() + retry_after);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket {
+            rate: 2.0,
+            capacity: 2.0,
+            tokens: 2.0,
+            updated_at: now,
+        };
+
+        // burst of two, then queued behind each other at 2/s
+        for expected_ms in [0, 0, 500, 1000] {
+            assert_eq!(bucket.wait(now), Duration::from_millis(expected_ms));
+            bucket.take();
+        }
+
+        // refilled, but never beyond capacity
+        let later = now + Duration::from_secs(10);
+        assert_eq!(bucket.wait(later), Duration::ZERO);
+        assert_eq!(bucket.tokens, 2.0);
+
+        bucket.pause(later + Duration::from_secs(3));
+        assert_eq!(bucket.wait(later), Duration::from_millis(3500));
+    }
+
+    #[tokio::test]
+    async fn test_shed_load() {
+        let limiter = RateLimiter::new(1.0, Duration::from_millis(100));
+        assert!(limiter.acquire().await.is_ok());
+        let err = limiter.acquire().await.unwrap_err();
+        assert!(err.is_rate_limited());
+    }
+
+    #[tokio::test]
+    async fn test_spare_never_queues() {
+        let limiter = RateLimiter::new(1.0, Duration::from_secs(2));
+        assert!(limiter.try_acquire_spare().await.is_ok());
+        let err = limiter.try_acquire_spare().await.unwrap_err();
+        assert!(err.is_rate_limited());
+        // the query left no debt, a send queues behind one token only
+        assert!(limiter.bucket.lock().await.tokens >= 0.0);
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use reqwest::Url;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use super::{error::JitoError, JITO};
+
+/// Outcome of sending a bundle to one block engine.
+#[derive(Debug)]
+pub struct RegionSubmission {
+    pub region: String, // block engine host, e.g. ny.mainnet.block-engine.jito.wtf
+    pub latency: Duration,
+    pub result: Result<String, JitoError>, // bundle id
+}
+
+/// A bundle sent to every configured block engine. `regions` holds the replies
+/// received up to the first acceptance, in reply order; later replies are only
+/// logged.
+#[derive(Debug)]
+pub struct BundleSubmission {
+    pub bundle_id: String, // from the first region that accepted
+    pub regions: Vec<RegionSubmission>,
+}
+
+impl BundleSubmission {
+    pub fn accepted_regions(&self) -> Vec<&str> {
+        self.regions
+            .iter()
+            .filter(|r| r.result.is_ok())
+            .map(|r| r.region.as_str())
+            .collect()
+    }
+}
+
+impl JITO {
+    /// Send `bundle` to all block engines at once and return as soon as one
+    /// accepts it, the rest finish in the background. Fails only when no
+    /// region accepted it, with the error of the first region.
+    pub async fn send_bundle(&self, bundle: Option<Value>) -> Result<BundleSubmission> {
+        let mut pending: FuturesUnordered<_> = self
+            .region_clients
+            .iter()
+            .map(|client| {
+                let client = client.clone();
+                let bundle = bundle.clone();
+                async move {
+                    let start_time = Instant::now();
+                    let result = client.send_bundle(bundle).await;
+                    RegionSubmission {
+                        region: region_name(client.base_url()),
+                        latency: start_time.elapsed(),
+                        result,
+                    }
+                }
+            })
+            .collect();
+
+        let mut regions = Vec::with_capacity(pending.len());
+        while let Some(submission) = pending.next().await {
+            log_submission(&submission);
+            let accepted = submission.result.is_ok();
+            regions.push(submission);
+            if accepted {
+                break;
+            }
+        }
+
+        if !pending.is_empty() {
+            // a slow or rate limited region must not gate the trade
+            tokio::spawn(async move {
+                while let Some(submission) = pending.next().await {
+                    log_submission(&submission);
+                }
+            });
+        }
+
+        first_accepted(regions)
+    }
+}
+
+fn log_submission(submission: &RegionSubmission) {
+    match &submission.result {
+        Ok(bundle_id) => info!(
+            "jito: {} accepted bundle {} in {:?}",
+            submission.region, bundle_id, submission.latency
+        ),
+        Err(e) => warn!(
+            "jito: {} rejected bundle in {:?}: {}",
+            submission.region, submission.latency, e
+        ),
+    }
+}
+
+fn first_accepted(regions: Vec<RegionSubmission>) -> Result<BundleSubmission> {
+    let bundle_id = regions.iter().find_map(|r| r.result.as_ref().ok()).cloned();
+    if let Some(bundle_id) = bundle_id {
+        return Ok(BundleSubmission { bundle_id, regions });
+    }
+    match regions.into_iter().find_map(|r| r.result.err()) {
+        Some(e) => Err(e.into()),
+        None => Err(anyhow!("jito: no block engine configured")),
+    }
+}
+
+fn region_name(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .unwrap_or_else(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jito::JITOConfig;
+
+    fn submission(region: &str, result: Result<String, JitoError>) -> RegionSubmission {
+        RegionSubmission {
+            region: region.to_string(),
+            latency: Duration::from_millis(10),
+            result,
+        }
+    }
+
+    #[test]
+    fn test_first_accepted() -> Result<()> {
+        let accepted = first_accepted(vec![
+            submission("ny", Err(JitoError::RateLimited { retry_after: None })),
+            submission("tokyo", Ok("b1".to_string())),
+            submission("amsterdam", Ok("b1".to_string())),
+        ])?;
+        assert_eq!(accepted.bundle_id, "b1");
+        assert_eq!(accepted.accepted_regions(), vec!["tokyo", "amsterdam"]);
+
+        let err = first_accepted(vec![submission(
+            "ny",
+            Err(JitoError::RateLimited { retry_after: None }),
+        )])
+        .unwrap_err();
+        assert!(err
+            .downcast_ref::<JitoError>()
+            .is_some_and(|e| e.is_rate_limited()));
+        assert!(first_accepted(vec![]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_engine_urls() -> Result<()> {
+        let config: JITOConfig = toml::from_str(
+            r#"
+            tips_percentile = 50
+            tip_stream_url = ""
+            jito_sdk_url = "https://ny.mainnet.block-engine.jito.wtf/api/v1"
+            jito_sdk_urls = [
+                "https://tokyo.mainnet.block-engine.jito.wtf/api/v1",
+                "https://ny.mainnet.block-engine.jito.wtf/api/v1",
+            ]
+            extra_tip = 0.0
+            slippage = 10
+            "#,
+        )?;
+        assert_eq!(
+            config.block_engine_urls(),
+            vec![
+                "https://ny.mainnet.block-engine.jito.wtf/api/v1",
+                "https://tokyo.mainnet.block-engine.jito.wtf/api/v1",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_region_name() {
+        assert_eq!(
+            region_name("https://ny.mainnet.block-engine.jito.wtf/api/v1"),
+            "ny.mainnet.block-engine.jito.wtf"
+        );
+        assert_eq!(region_name("not a url"), "not a url");
+    }
+}
@@ -1,14 +1,31 @@
 use anyhow::{anyhow, Result};
 use rand::seq::SliceRandom;
-use reqwest::Client;
+use reqwest::{header::RETRY_AFTER, Client, StatusCode};
+use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
-use std::fmt;
+use std::{fmt, sync::Arc, time::Duration};
 use tracing::trace;
 
+use super::{
+    error::JitoError,
+    rate_limit::RateLimiter,
+    types::{BundleStatuses, InflightBundleStatuses, JsonRpcResponse, SimulateBundleResponse},
+};
+
+pub const JITO_AUTH_HEADER: &str = "x-jito-auth";
+// pause after a 429 without retry-after
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+// a hung block engine must not stall the send
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// polled in the background, only sent when the rate limit has room to spare
+const BACKGROUND_METHODS: [&str; 2] = ["getBundleStatuses", "getInflightBundleStatuses"];
+
 pub struct JitoJsonRpcSDK {
     base_url: String,
-    uuid: Option<String>,
+    uuid: Option<String>, // auth uuid, sent with every request
     client: Client,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 #[derive(Debug)]
@@ -31,17 +48,31 @@ impl JitoJsonRpcSDK {
         Self {
             base_url: base_url.to_string(),
             uuid,
-            client: Client::new(),
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .expect("jito: build http client"),
+            rate_limiter: None,
         }
     }
 
-    async fn send_request(
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn send_request<T: DeserializeOwned>(
         &self,
         endpoint: &str,
         method: &str,
         params: Option<Value>,
-    ) -> Result<Value, reqwest::Error> {
-        let url = format!("{}{}", self.base_url, endpoint);
+    ) -> Result<T, JitoError> {
+        let url = self.url(endpoint);
 
         let data = json!({
             "jsonrpc": "2.0",
@@ -56,98 +87,102 @@ impl JitoJsonRpcSDK {
             serde_json::to_string_pretty(&data).unwrap()
         );
 
-        let response = self
+        if let Some(rate_limiter) = &self.rate_limiter {
+            if BACKGROUND_METHODS.contains(&method) {
+                rate_limiter.try_acquire_spare().await?;
+            } else {
+                rate_limiter.acquire().await?;
+            }
+        }
+
+        let mut request = self
             .client
             .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&data)
-            .send()
-            .await?;
+            .header("Content-Type", "application/json");
+        if let Some(uuid) = &self.uuid {
+            request = request.header(JITO_AUTH_HEADER, uuid);
+        }
+        let response = request.json(&data).send().await?;
 
         let status = response.status();
         trace!("Response status: {}", status);
-
-        let body = response.json::<Value>().await?;
-        trace!(
-            "Response body: {}",
-            serde_json::to_string_pretty(&body).unwrap()
-        );
-
-        Ok(body)
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        let body = response.text().await?;
+        trace!("Response body: {}", body);
+
+        let result = parse_response(status, retry_after, &body);
+        if let (Some(rate_limiter), Err(e)) = (&self.rate_limiter, &result) {
+            if e.is_rate_limited() {
+                rate_limiter
+                    .throttled(retry_after.unwrap_or(DEFAULT_RETRY_AFTER))
+                    .await;
+            }
+        }
+        result
     }
 
-    pub async fn get_tip_accounts(&self) -> Result<Value, reqwest::Error> {
-        let endpoint = if let Some(uuid) = &self.uuid {
-            format!("/bundles?uuid={}", uuid)
-        } else {
-            "/bundles".to_string()
-        };
+    // `endpoint` under the base url, with the auth uuid in the query as well
+    fn url(&self, endpoint: &str) -> String {
+        match &self.uuid {
+            Some(uuid) => {
+                let separator = if endpoint.contains('?') { '&' } else { '?' };
+                format!("{}{}{}uuid={}", self.base_url, endpoint, separator, uuid)
+            }
+            None => format!("{}{}", self.base_url, endpoint),
+        }
+    }
 
-        self.send_request(&endpoint, "getTipAccounts", None).await
+    pub async fn get_tip_accounts(&self) -> Result<Vec<String>, JitoError> {
+        self.send_request("/bundles", "getTipAccounts", None).await
     }
 
     // Get a random tip account
     pub async fn get_random_tip_account(&self) -> Result<String> {
-        let tip_accounts_response = self.get_tip_accounts().await?;
-
-        let tip_accounts = tip_accounts_response["result"]
-            .as_array()
-            .ok_or_else(|| anyhow!("Failed to parse tip accounts as array"))?;
+        let tip_accounts = self.get_tip_accounts().await?;
 
-        if tip_accounts.is_empty() {
-            return Err(anyhow!("No tip accounts available"));
-        }
-
-        let random_account = tip_accounts
+        tip_accounts
             .choose(&mut rand::thread_rng())
-            .ok_or_else(|| anyhow!("Failed to choose random tip account"))?;
-
-        random_account
-            .as_str()
-            .ok_or_else(|| anyhow!("Failed to parse tip account as string"))
-            .map(String::from)
+            .cloned()
+            .ok_or_else(|| anyhow!("No tip accounts available"))
     }
 
-    pub async fn get_bundle_statuses(&self, bundle_uuids: Vec<String>) -> Result<Value> {
-        let endpoint = if let Some(uuid) = &self.uuid {
-            format!("/bundles?uuid={}", uuid)
-        } else {
-            "/bundles".to_string()
-        };
-
+    pub async fn get_bundle_statuses(
+        &self,
+        bundle_uuids: Vec<String>,
+    ) -> Result<BundleStatuses, JitoError> {
         // Construct the params as a list within a list
         let params = json!([bundle_uuids]);
 
-        self.send_request(&endpoint, "getBundleStatuses", Some(params))
+        self.send_request("/bundles", "getBundleStatuses", Some(params))
             .await
-            .map_err(|e| anyhow!("Request error: {}", e))
     }
 
-    pub async fn send_bundle(
-        &self,
-        params: Option<Value>,
-        uuid: Option<&str>,
-    ) -> Result<Value, anyhow::Error> {
-        let mut endpoint = "/bundles".to_string();
-
-        if let Some(uuid) = uuid {
-            endpoint = format!("{}?uuid={}", endpoint, uuid);
-        }
-
+    /// Send up to 5 serialized transactions as a bundle, returns the bundle id.
+    pub async fn send_bundle(&self, params: Option<Value>) -> Result<String, JitoError> {
         // Ensure params is an array of transactions
         let transactions = match params {
             Some(Value::Array(transactions)) => {
                 if transactions.is_empty() {
-                    return Err(anyhow!("Bundle must contain at least one transaction"));
+                    return Err(JitoError::InvalidRequest(
+                        "Bundle must contain at least one transaction".to_string(),
+                    ));
                 }
                 if transactions.len() > 5 {
-                    return Err(anyhow!("Bundle can contain at most 5 transactions"));
+                    return Err(JitoError::InvalidRequest(
+                        "Bundle can contain at most 5 transactions".to_string(),
+                    ));
                 }
                 transactions
             }
             _ => {
-                return Err(anyhow!(
-                    "Invalid bundle format: expected an array of transactions"
+                return Err(JitoError::InvalidRequest(
+                    "Invalid bundle format: expected an array of transactions".to_string(),
                 ))
             }
         };
@@ -156,16 +191,16 @@ impl JitoJsonRpcSDK {
         let params = json!([transactions]);
 
         // Send the wrapped transactions array
-        self.send_request(&endpoint, "sendBundle", Some(params))
+        self.send_request("/bundles", "sendBundle", Some(params))
             .await
-            .map_err(|e| anyhow!("Request error: {}", e))
     }
 
+    /// Send a single transaction through the block engine, returns its signature.
     pub async fn send_txn(
         &self,
         params: Option<Value>,
         bundle_only: bool,
-    ) -> Result<Value, reqwest::Error> {
+    ) -> Result<String, JitoError> {
         let mut query_params = Vec::new();
 
         if bundle_only {
@@ -201,19 +236,40 @@ impl JitoJsonRpcSDK {
             .await
     }
 
-    pub async fn get_in_flight_bundle_statuses(&self, bundle_uuids: Vec<String>) -> Result<Value> {
-        let endpoint = if let Some(uuid) = &self.uuid {
-            format!("/bundles?uuid={}", uuid)
-        } else {
-            "/bundles".to_string()
-        };
-
+    pub async fn get_in_flight_bundle_statuses(
+        &self,
+        bundle_uuids: Vec<String>,
+    ) -> Result<InflightBundleStatuses, JitoError> {
         // Construct the params as a list within a list
         let params = json!([bundle_uuids]);
 
-        self.send_request(&endpoint, "getInflightBundleStatuses", Some(params))
+        self.send_request("/bundles", "getInflightBundleStatuses", Some(params))
             .await
-            .map_err(|e| anyhow!("Request error: {}", e))
+    }
+
+    /// Simulate base64 encoded transactions as one bundle. Served by
+    /// jito-solana rpc nodes, so `base_url` is such a node's url.
+    pub async fn simulate_bundle(
+        &self,
+        transactions: Vec<String>,
+    ) -> Result<SimulateBundleResponse, JitoError> {
+        if transactions.is_empty() {
+            return Err(JitoError::InvalidRequest(
+                "Bundle must contain at least one transaction".to_string(),
+            ));
+        }
+        let accounts_configs = vec![Value::Null; transactions.len()];
+        let params = json!([
+            { "encodedTransactions": transactions },
+            {
+                "preExecutionAccountsConfigs": accounts_configs,
+                "postExecutionAccountsConfigs": accounts_configs,
+                "skipSigVerify": false,
+                "replaceRecentBlockhash": false
+            }
+        ]);
+
+        self.send_request("", "simulateBundle", Some(params)).await
     }
 
     // Helper method to convert Value to PrettyJsonValue
@@ -221,3 +277,136 @@ impl JitoJsonRpcSDK {
         PrettyJsonValue(value)
     }
 }
+
+/// Map an http response of the block engine to its result or a `JitoError`.
+pub fn parse_response<T: DeserializeOwned>(
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+) -> Result<T, JitoError> {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(JitoError::RateLimited { retry_after });
+    }
+    if status.is_server_error() {
+        return Err(JitoError::Server {
+            status: status.as_u16(),
+            body: body.to_string(),
+        });
+    }
+
+    let response = match serde_json::from_str::<JsonRpcResponse<T>>(body) {
+        Ok(response) => response,
+        Err(_) if !status.is_success() => {
+            return Err(JitoError::Http {
+                status: status.as_u16(),
+                body: body.to_string(),
+            })
+        }
+        Err(e) => return Err(JitoError::InvalidResponse(format!("{}: {}", e, body))),
+    };
+
+    match (response.result, response.error) {
+        (_, Some(error)) => Err(JitoError::Rpc {
+            code: error.code,
+            message: error.message,
+            data: error.data,
+        }),
+        (Some(result), None) if status.is_success() => Ok(result),
+        (Some(_), None) => Err(JitoError::Http {
+            status: status.as_u16(),
+            body: body.to_string(),
+        }),
+        (None, None) => Err(JitoError::InvalidResponse(format!(
+            "neither result nor error: {}",
+            body
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jito::types::InflightStatus;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    #[test]
+    fn test_parse_result() -> Result<()> {
+        let body = r#"{"jsonrpc":"2.0","result":"b1","id":1}"#;
+        let bundle_id: String = parse_response(StatusCode::OK, None, body)?;
+        assert_eq!(bundle_id, "b1");
+
+        let body = r#"{"jsonrpc":"2.0","result":{"context":{"slot":280999028},"value":[
+            {"bundle_id":"b1","transactions":["sig1"],"slot":280999025,
+             "confirmation_status":"finalized","err":{"Ok":null}},
+            null]},"id":1}"#;
+        let statuses: BundleStatuses = parse_response(StatusCode::OK, None, body)?;
+        assert_eq!(statuses.context.slot, 280999028);
+        let status = statuses.value[0].as_ref().unwrap();
+        assert_eq!(status.transactions, vec!["sig1"]);
+        assert_eq!(
+            status.confirmation_status,
+            Some(TransactionConfirmationStatus::Finalized)
+        );
+        assert!(statuses.value[1].is_none());
+
+        let body = r#"{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":[
+            {"bundle_id":"b1","status":"Landed","landed_slot":10},
+            {"bundle_id":"b2","status":"Something","landed_slot":null}]},"id":1}"#;
+        let statuses: InflightBundleStatuses = parse_response(StatusCode::OK, None, body)?;
+        let landed = statuses.value[0].as_ref().unwrap();
+        assert_eq!(landed.status, InflightStatus::Landed);
+        assert_eq!(landed.landed_slot, Some(10));
+        assert_eq!(
+            statuses.value[1].as_ref().unwrap().status,
+            InflightStatus::Unknown
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_url_with_uuid() {
+        let sdk = JitoJsonRpcSDK::new("https://jito/api/v1", Some("abc".to_string()));
+        assert_eq!(sdk.url("/bundles"), "https://jito/api/v1/bundles?uuid=abc");
+        assert_eq!(
+            sdk.url("/transactions?bundleOnly=true"),
+            "https://jito/api/v1/transactions?bundleOnly=true&uuid=abc"
+        );
+        let sdk = JitoJsonRpcSDK::new("https://jito/api/v1", None);
+        assert_eq!(sdk.url("/bundles"), "https://jito/api/v1/bundles");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let body = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"bundle contains an already processed transaction"},"id":1}"#;
+        let err = parse_response::<String>(StatusCode::BAD_REQUEST, None, body).unwrap_err();
+        assert!(matches!(err, JitoError::Rpc { code: -32602, .. }));
+        assert!(!err.is_retryable());
+
+        let body =
+            r#"{"jsonrpc":"2.0","error":{"code":-32097,"message":"Network congested"},"id":1}"#;
+        let err = parse_response::<String>(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(1)),
+            body,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            JitoError::RateLimited {
+                retry_after: Some(_)
+            }
+        ));
+        assert!(err.is_rate_limited());
+
+        let err =
+            parse_response::<String>(StatusCode::BAD_GATEWAY, None, "bad gateway").unwrap_err();
+        assert!(matches!(err, JitoError::Server { status: 502, .. }));
+        assert!(err.is_retryable());
+
+        let err = parse_response::<String>(StatusCode::FORBIDDEN, None, "forbidden").unwrap_err();
+        assert!(matches!(err, JitoError::Http { status: 403, .. }));
+
+        let err = parse_response::<String>(StatusCode::OK, None, "{}").unwrap_err();
+        assert!(matches!(err, JitoError::InvalidResponse(_)));
+    }
+}
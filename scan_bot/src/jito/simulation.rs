@@ -0,0 +1,175 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use tracing::{info, warn};
+
+use super::{
+    sdk::JitoJsonRpcSDK,
+    types::{SimulateBundleResponse, SimulateBundleSummary},
+};
+use crate::sol_client::landing::decode_bundle_error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionSimulation {
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    pub error: Option<String>, // decoded program error
+}
+
+/// Outcome of `simulateBundle`, transactions in bundle order up to the
+/// first failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleSimulationReport {
+    pub slot: u64,
+    pub error: Option<String>, // None when the whole bundle succeeded
+    pub failed_signature: Option<String>,
+    pub transactions: Vec<TransactionSimulation>,
+}
+
+impl BundleSimulationReport {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn units_consumed(&self) -> u64 {
+        self.transactions
+            .iter()
+            .filter_map(|t| t.units_consumed)
+            .sum()
+    }
+
+    pub fn log(&self) {
+        for (i, tx) in self.transactions.iter().enumerate() {
+            for log in &tx.logs {
+                info!("Simulate tx {} log: {}", i, log);
+            }
+        }
+        info!("Simulate bundle: {}", self);
+    }
+}
+
+impl fmt::Display for BundleSimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.error, &self.failed_signature) {
+            (None, _) => write!(f, "succeeded")?,
+            (Some(error), Some(signature)) => write!(f, "failed at {}: {}", signature, error)?,
+            (Some(error), None) => write!(f, "failed: {}", error)?,
+        }
+        write!(
+            f,
+            ", slot: {}, {} transactions, {} compute units",
+            self.slot,
+            self.transactions.len(),
+            self.units_consumed()
+        )
+    }
+}
+
+impl From<SimulateBundleResponse> for BundleSimulationReport {
+    fn from(response: SimulateBundleResponse) -> Self {
+        let transactions: Vec<TransactionSimulation> = response
+            .value
+            .transaction_results
+            .into_iter()
+            .map(|tx| TransactionSimulation {
+                logs: tx.logs.unwrap_or_default(),
+                units_consumed: tx.units_consumed,
+                error: tx.err.as_ref().and_then(decode_bundle_error),
+            })
+            .collect();
+
+        let (error, failed_signature) = match response.value.summary {
+            SimulateBundleSummary::Succeeded => (None, None),
+            SimulateBundleSummary::Failed {
+                error,
+                tx_signature,
+            } => {
+                // the failed transaction's program error says more than the summary
+                let error = transactions
+                    .iter()
+                    .find_map(|t| t.error.clone())
+                    .unwrap_or_else(|| error.to_string());
+                (Some(error), tx_signature)
+            }
+        };
+
+        Self {
+            slot: response.context.slot,
+            error,
+            failed_signature,
+            transactions,
+        }
+    }
+}
+
+/// Simulate base64 encoded `transactions` as one bundle on `client`.
+pub async fn simulate_bundle(
+    client: &JitoJsonRpcSDK,
+    transactions: Vec<String>,
+) -> Result<BundleSimulationReport> {
+    let report = BundleSimulationReport::from(client.simulate_bundle(transactions).await?);
+    report.log();
+    Ok(report)
+}
+
+/// Simulate and fail when the bundle would fail, so it is never sent.
+pub async fn ensure_bundle_succeeds(
+    client: &JitoJsonRpcSDK,
+    transactions: Vec<String>,
+) -> Result<BundleSimulationReport> {
+    let report = simulate_bundle(client, transactions).await?;
+    if !report.succeeded() {
+        warn!("Bundle simulation failed, not sending: {}", report);
+        return Err(anyhow!("jito: bundle simulation {}", report));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jito::sdk::parse_response;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_simulation_report() -> Result<()> {
+        let body = r#"{"jsonrpc":"2.0","result":{"context":{"slot":100},"value":{
+            "summary":"succeeded",
+            "transactionResults":[
+                {"err":null,"logs":["Program log: Instruction: Buy"],"unitsConsumed":40000,
+                 "preExecutionAccounts":null,"postExecutionAccounts":null,"returnData":null},
+                {"err":null,"logs":[],"unitsConsumed":150}
+            ]}},"id":1}"#;
+        let report = BundleSimulationReport::from(parse_response::<SimulateBundleResponse>(
+            StatusCode::OK,
+            None,
+            body,
+        )?);
+        assert!(report.succeeded());
+        assert_eq!(report.slot, 100);
+        assert_eq!(report.units_consumed(), 40150);
+        assert_eq!(report.transactions[0].logs.len(), 1);
+
+        let body = r#"{"jsonrpc":"2.0","result":{"context":{"slot":101},"value":{
+            "summary":{"failed":{"error":{"TransactionFailure":[[1,2],"custom program error: 0x1772"]},"txSignature":"sig1"}},
+            "transactionResults":[
+                {"err":{"InstructionError":[2,{"Custom":6002}]},"logs":["Program log: Error"],"unitsConsumed":30000}
+            ]}},"id":1}"#;
+        let report = BundleSimulationReport::from(parse_response::<SimulateBundleResponse>(
+            StatusCode::OK,
+            None,
+            body,
+        )?);
+        assert!(!report.succeeded());
+        assert_eq!(report.failed_signature.as_deref(), Some("sig1"));
+        assert_eq!(
+            report.error.as_deref(),
+            Some("instruction 2: TooMuchSolRequired (6002)")
+        );
+        assert_eq!(
+            report.to_string(),
+            "failed at sig1: instruction 2: TooMuchSolRequired (6002), slot: 101, 1 transactions, 30000 compute units"
+        );
+        Ok(())
+    }
+}
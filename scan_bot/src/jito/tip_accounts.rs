@@ -0,0 +1,85 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use rand::seq::SliceRandom;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{debug, warn};
+
+use super::{sdk::JitoJsonRpcSDK, JITO};
+
+// tip accounts rarely change, a slow refresh is enough
+pub const TIP_ACCOUNTS_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+pub static TIP_ACCOUNTS: OnceCell<Arc<RwLock<Vec<Pubkey>>>> = OnceCell::const_new();
+
+pub async fn get_tip_accounts() -> &'static Arc<RwLock<Vec<Pubkey>>> {
+    TIP_ACCOUNTS
+        .get_or_init(|| async { Arc::new(RwLock::new(vec![])) })
+        .await
+}
+
+fn parse_tip_accounts(accounts: &[String]) -> Result<Vec<Pubkey>> {
+    if accounts.is_empty() {
+        return Err(anyhow!("jito: no tip accounts available"));
+    }
+    accounts
+        .iter()
+        .map(|a| Pubkey::from_str(a).map_err(|e| anyhow!("jito: invalid tip account {}: {}", a, e)))
+        .collect()
+}
+
+/// Reload the cached tip accounts, the last known set is kept on failure.
+pub async fn refresh_tip_accounts(client: &JitoJsonRpcSDK) -> Result<()> {
+    let accounts = parse_tip_accounts(&client.get_tip_accounts().await?)?;
+    debug!("jito: loaded {} tip accounts", accounts.len());
+    *get_tip_accounts().await.write().await = accounts;
+    Ok(())
+}
+
+/// Keep the tip account cache fresh, loads it right away.
+pub async fn tip_accounts_refresher() {
+    let client = JITO::default_client().await.client;
+    loop {
+        if let Err(e) = refresh_tip_accounts(&client).await {
+            let known = get_tip_accounts().await.read().await.len();
+            warn!(
+                "jito: failed to refresh tip accounts, keeping {} known: {}",
+                known, e
+            );
+        }
+        tokio::time::sleep(TIP_ACCOUNTS_REFRESH_INTERVAL).await;
+    }
+}
+
+/// Random tip account from the cache, fetched only when nothing is cached yet.
+pub async fn random_tip_account(client: &JitoJsonRpcSDK) -> Result<Pubkey> {
+    if let Some(account) = choose_tip_account(&get_tip_accounts().await.read().await) {
+        return Ok(account);
+    }
+    refresh_tip_accounts(client).await?;
+    choose_tip_account(&get_tip_accounts().await.read().await)
+        .ok_or_else(|| anyhow!("jito: no tip accounts available"))
+}
+
+fn choose_tip_account(accounts: &[Pubkey]) -> Option<Pubkey> {
+    accounts.choose(&mut rand::thread_rng()).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tip_accounts() -> Result<()> {
+        let account = Pubkey::new_unique();
+        let accounts = parse_tip_accounts(&[account.to_string()])?;
+        assert_eq!(accounts, vec![account]);
+        assert_eq!(choose_tip_account(&accounts), Some(account));
+        assert_eq!(choose_tip_account(&[]), None);
+
+        assert!(parse_tip_accounts(&[]).is_err());
+        assert!(parse_tip_accounts(&["not a pubkey".to_string()]).is_err());
+        Ok(())
+    }
+}
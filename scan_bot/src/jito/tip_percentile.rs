@@ -1,15 +1,32 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use anyhow::{Context, Result};
-use futures_util::StreamExt;
+use anyhow::{anyhow, Context, Result};
+use futures_util::{SinkExt, StreamExt};
 use serde::Deserialize;
-use tokio::sync::{OnceCell, RwLock};
+use tokio::{
+    sync::{OnceCell, RwLock},
+    time::{interval, timeout, MissedTickBehavior},
+};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{debug, error, info, warn};
 
-use crate::config::get_global_config;
+use crate::{
+    config::get_global_config,
+    sol_client::subscription::{
+        next_reconnect_delay, CONNECT_TIMEOUT, DEAD_SOCKET_TIMEOUT, MIN_RECONNECT_DELAY,
+        PING_INTERVAL,
+    },
+};
+
+pub static TIP_PERCENTILE: OnceCell<Arc<RwLock<Option<TipUpdate>>>> = OnceCell::const_new();
+static TIP_STREAM_STARTED: AtomicBool = AtomicBool::new(false);
 
-pub static TIP_PERCENTILE: OnceCell<Arc<RwLock<Option<TipPercentileData>>>> = OnceCell::const_new();
 #[derive(Debug, Deserialize, Clone)]
 pub struct TipPercentileData {
     pub time: String,
@@ -21,52 +38,163 @@ pub struct TipPercentileData {
     pub ema_landed_tips_50th_percentile: f64,
 }
 
-pub async fn get_tip_percentile() -> &'static Arc<RwLock<Option<TipPercentileData>>> {
+#[derive(Debug, Clone)]
+pub struct TipUpdate {
+    pub data: TipPercentileData,
+    pub received_at: Instant,
+}
+
+pub async fn get_tip_percentile() -> &'static Arc<RwLock<Option<TipUpdate>>> {
     TIP_PERCENTILE
         .get_or_init(|| async { Arc::new(RwLock::new(None)) })
         .await
 }
 
-pub async fn tip_stream() -> Result<()> {
+/// Data of the latest update, unless it is older than `max_age`.
+pub fn fresh_tip_data(
+    update: Option<&TipUpdate>,
+    max_age: Duration,
+    now: Instant,
+) -> Result<&TipPercentileData> {
+    match update {
+        Some(update) if now.duration_since(update.received_at) <= max_age => Ok(&update.data),
+        Some(update) => Err(anyhow!(
+            "jito: tip percentile data is stale, last update {:?} ago",
+            now.duration_since(update.received_at)
+        )),
+        None => Err(anyhow!("jito: no tip percentile data available")),
+    }
+}
+
+/// Start the tip stream service, once per process.
+pub fn spawn_tip_stream() {
+    if TIP_STREAM_STARTED.swap(true, Ordering::SeqCst) {
+        debug!("tip stream already running");
+        return;
+    }
+    tokio::spawn(tip_stream());
+}
+
+/// Keep the tip stream connected, reconnecting with backoff. Prefer
+/// `spawn_tip_stream`, which never runs two of these.
+pub async fn tip_stream() {
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        match connect_tip_stream().await {
+            // received data, the connection was healthy
+            Ok(true) => delay = MIN_RECONNECT_DELAY,
+            Ok(false) => warn!("tip stream closed without data"),
+            Err(e) => error!("tip stream error: {:?}", e),
+        }
+        warn!("tip stream disconnected, reconnecting in {:?}", delay);
+        tokio::time::sleep(delay).await;
+        delay = next_reconnect_delay(delay);
+    }
+}
+
+// returns whether any tip data was received before the connection ended;
+// a silent socket, or one that sends no tips for `tip_max_age_secs`, is an
+// error so the caller reconnects
+async fn connect_tip_stream() -> Result<bool> {
     let c = get_global_config().await;
-    let (ws_stream, _) = connect_async(&c.jito_config.tip_stream_url)
-        .await
-        .context("Failed to connect to WebSocket server")?;
+    let (ws_stream, _) = timeout(
+        CONNECT_TIMEOUT,
+        connect_async(&c.jito_config.tip_stream_url),
+    )
+    .await
+    .context("tip stream connect timed out")?
+    .context("Failed to connect to WebSocket server")?;
 
     info!("Connected to WebSocket server: tip_stream");
 
-    let (mut _write, mut read) = ws_stream.split();
+    let (mut write, mut read) = ws_stream.split();
+    let max_age = Duration::from_secs(c.jito_config.tip_max_age_secs);
+    let mut ping = interval(PING_INTERVAL);
+    ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_seen = Instant::now();
+    let mut last_tip = Instant::now();
+    let mut received = false;
 
-    while let Some(message) = read.next().await {
-        match message {
-            Ok(Message::Text(text)) => {
-                debug!("Received text message: {}", text);
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                if last_seen.elapsed() > DEAD_SOCKET_TIMEOUT {
+                    return Err(anyhow!("tip stream: nothing received for {:?}, socket is dead", last_seen.elapsed()));
+                }
+                if last_tip.elapsed() > max_age {
+                    return Err(anyhow!("tip stream: no tip data for {:?}", last_tip.elapsed()));
+                }
+                write.send(Message::Ping(Default::default())).await?;
+            }
+            message = read.next() => {
+                let Some(message) = message else {
+                    return Ok(received);
+                };
+                last_seen = Instant::now();
+                match message {
+                    Ok(Message::Text(text)) => {
+                        debug!("Received text message: {}", text);
 
-                match serde_json::from_str::<Vec<TipPercentileData>>(&text) {
-                    Ok(data) => {
-                        if !data.is_empty() {
-                            let tp = get_tip_percentile().await;
-                            tp.write().await.replace(data[0].clone());
-                        } else {
-                            warn!("Received an empty data.")
+                        match serde_json::from_str::<Vec<TipPercentileData>>(&text) {
+                            Ok(data) => {
+                                if !data.is_empty() {
+                                    let tp = get_tip_percentile().await;
+                                    tp.write().await.replace(TipUpdate {
+                                        data: data[0].clone(),
+                                        received_at: Instant::now(),
+                                    });
+                                    last_tip = Instant::now();
+                                    received = true;
+                                } else {
+                                    warn!("Received an empty data.")
+                                }
+                            }
+                            Err(e) => {
+                                error!("Failed to deserialize JSON: {:?}", e);
+                            }
                         }
                     }
+                    Ok(Message::Close(close)) => {
+                        info!("Connection closed: {:?}", close);
+                        return Ok(received);
+                    }
                     Err(e) => {
-                        error!("Failed to deserialize JSON: {:?}", e);
+                        error!("Error receiving message: {:?}", e);
+                        return Ok(received);
                     }
+                    // pongs only count as a sign of life
+                    _ => {}
                 }
             }
-            Ok(Message::Close(close)) => {
-                info!("Connection closed: {:?}", close);
-                break;
-            }
-            Err(e) => {
-                error!("Error receiving message: {:?}", e);
-                break;
-            }
-            _ => {}
         }
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_tip_data() {
+        let now = Instant::now();
+        let max_age = Duration::from_secs(60);
+        let update = TipUpdate {
+            data: TipPercentileData {
+                time: String::new(),
+                landed_tips_25th_percentile: 0.00001,
+                landed_tips_50th_percentile: 0.00002,
+                landed_tips_75th_percentile: 0.00003,
+                landed_tips_95th_percentile: 0.00004,
+                landed_tips_99th_percentile: 0.00005,
+                ema_landed_tips_50th_percentile: 0.00002,
+            },
+            received_at: now,
+        };
+
+        let data = fresh_tip_data(Some(&update), max_age, now).unwrap();
+        assert_eq!(data.landed_tips_50th_percentile, 0.00002);
+        let later = now + Duration::from_secs(61);
+        assert!(fresh_tip_data(Some(&update), max_age, later).is_err());
+        assert!(fresh_tip_data(None, max_age, now).is_err());
+    }
 }
@@ -0,0 +1,252 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tracing::warn;
+
+use super::tip_percentile::TipPercentileData;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// How badly a trade needs to land, scales the policy tip.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Urgency {
+    Low, // half the tip
+    #[default]
+    Normal,
+    High,    // double the tip
+    Highest, // 4x the tip
+}
+
+impl Urgency {
+    pub fn multiplier(&self) -> f64 {
+        match self {
+            Urgency::Low => 0.5,
+            Urgency::Normal => 1.0,
+            Urgency::High => 2.0,
+            Urgency::Highest => 4.0,
+        }
+    }
+}
+
+/// Per-trade input of a tip policy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TipRequest {
+    pub trade_lamports: u64, // sol spent or received by the trade
+    pub urgency: Urgency,
+}
+
+/// Everything a policy may price a tip from.
+#[derive(Debug, Clone, Copy)]
+pub struct TipContext<'a> {
+    pub tips: Option<&'a TipPercentileData>, // None when missing or stale
+    pub request: TipRequest,
+}
+
+impl TipContext<'_> {
+    fn tips(&self) -> Result<&TipPercentileData> {
+        self.tips
+            .ok_or_else(|| anyhow!("jito: no fresh tip percentile data available"))
+    }
+}
+
+pub trait TipPolicy: Send + Sync {
+    /// Tip in sol before urgency and bounds are applied.
+    fn tip(&self, ctx: &TipContext) -> Result<f64>;
+}
+
+/// Any percentile of landed tips, linearly interpolated between the
+/// 25/50/75/95/99th points of the tip stream.
+pub struct PercentileTip {
+    pub percentile: f64,
+}
+
+impl TipPolicy for PercentileTip {
+    fn tip(&self, ctx: &TipContext) -> Result<f64> {
+        let data = ctx.tips()?;
+        let points = [
+            (25.0, data.landed_tips_25th_percentile),
+            (50.0, data.landed_tips_50th_percentile),
+            (75.0, data.landed_tips_75th_percentile),
+            (95.0, data.landed_tips_95th_percentile),
+            (99.0, data.landed_tips_99th_percentile),
+        ];
+        let p = self.percentile.clamp(points[0].0, points[4].0);
+        let tip = points
+            .windows(2)
+            .find(|w| p <= w[1].0)
+            .map(|w| {
+                let ((p0, t0), (p1, t1)) = (w[0], w[1]);
+                t0 + (t1 - t0) * (p - p0) / (p1 - p0)
+            })
+            .unwrap_or(points[4].1);
+        Ok(tip)
+    }
+}
+
+/// EMA of the 50th percentile, smoother than the raw percentiles.
+pub struct EmaTip;
+
+impl TipPolicy for EmaTip {
+    fn tip(&self, ctx: &TipContext) -> Result<f64> {
+        Ok(ctx.tips()?.ema_landed_tips_50th_percentile)
+    }
+}
+
+pub struct FixedTip {
+    pub amount: f64, // sol
+}
+
+impl TipPolicy for FixedTip {
+    fn tip(&self, _ctx: &TipContext) -> Result<f64> {
+        Ok(self.amount)
+    }
+}
+
+/// A share of the trade size, e.g. 0.01 tips 1% of the sol traded.
+pub struct TradeSizeTip {
+    pub multiple: f64,
+}
+
+impl TipPolicy for TradeSizeTip {
+    fn tip(&self, ctx: &TipContext) -> Result<f64> {
+        Ok(ctx.request.trade_lamports as f64 / LAMPORTS_PER_SOL * self.multiple)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum TipPolicyKind {
+    Percentile { percentile: f64 },
+    Ema,
+    Fixed { amount: f64 },
+    TradeSize { multiple: f64 },
+}
+
+impl TipPolicyKind {
+    pub fn build(&self) -> Box<dyn TipPolicy> {
+        match *self {
+            TipPolicyKind::Percentile { percentile } => Box::new(PercentileTip { percentile }),
+            TipPolicyKind::Ema => Box::new(EmaTip),
+            TipPolicyKind::Fixed { amount } => Box::new(FixedTip { amount }),
+            TipPolicyKind::TradeSize { multiple } => Box::new(TradeSizeTip { multiple }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TipPolicyConfig {
+    #[serde(flatten)]
+    pub kind: TipPolicyKind,
+    #[serde(default)]
+    pub floor: f64, // sol
+    #[serde(default = "default_ceiling")]
+    pub ceiling: f64, // sol
+}
+
+pub fn default_ceiling() -> f64 {
+    0.2
+}
+
+impl TipPolicyConfig {
+    /// Policy tip scaled by urgency and clamped to floor and ceiling, unit
+    /// sol. `default_tip` replaces the policy tip when it can't be priced.
+    pub fn tip_value(&self, ctx: &TipContext, default_tip: Option<f64>) -> Result<f64> {
+        let tip = match (self.kind.build().tip(ctx), default_tip) {
+            (Ok(tip), _) => tip,
+            (Err(e), Some(default_tip)) => {
+                warn!("{}, using default tip {} SOL", e, default_tip);
+                default_tip
+            }
+            (Err(e), None) => return Err(e),
+        };
+        Ok(self.clamp(tip * ctx.request.urgency.multiplier()))
+    }
+
+    /// `tip` within floor and ceiling, the ceiling is a hard cap.
+    pub fn clamp(&self, tip: f64) -> f64 {
+        tip.clamp(self.floor, self.ceiling.max(self.floor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tips() -> TipPercentileData {
+        TipPercentileData {
+            time: String::new(),
+            landed_tips_25th_percentile: 0.001,
+            landed_tips_50th_percentile: 0.002,
+            landed_tips_75th_percentile: 0.004,
+            landed_tips_95th_percentile: 0.008,
+            landed_tips_99th_percentile: 0.01,
+            ema_landed_tips_50th_percentile: 0.003,
+        }
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_percentile_tip() -> Result<()> {
+        let data = tips();
+        let ctx = TipContext {
+            tips: Some(&data),
+            request: TipRequest::default(),
+        };
+        let tip = |percentile| PercentileTip { percentile }.tip(&ctx);
+        assert_close(tip(50.0)?, 0.002);
+        assert_close(tip(60.0)?, 0.0028);
+        assert_close(tip(97.0)?, 0.009);
+        assert_close(tip(10.0)?, 0.001);
+        assert_close(tip(100.0)?, 0.01);
+
+        let ctx = TipContext {
+            tips: None,
+            request: TipRequest::default(),
+        };
+        assert!(PercentileTip { percentile: 50.0 }.tip(&ctx).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tip_value() -> Result<()> {
+        let data = tips();
+        let ctx = TipContext {
+            tips: Some(&data),
+            request: TipRequest {
+                trade_lamports: 2_000_000_000,
+                urgency: Urgency::High,
+            },
+        };
+
+        let config: TipPolicyConfig = toml::from_str(
+            r#"
+            type = "TradeSize"
+            multiple = 0.01
+            floor = 0.0001
+            ceiling = 0.03
+            "#,
+        )?;
+        assert_eq!(config.kind, TipPolicyKind::TradeSize { multiple: 0.01 });
+        // 1% of 2 sol, doubled
+        assert_close(config.tip_value(&ctx, None)?, 0.04_f64.min(0.03));
+
+        let config: TipPolicyConfig = toml::from_str(r#"type = "Ema""#)?;
+        assert_eq!(config.ceiling, default_ceiling());
+        assert_close(config.tip_value(&ctx, None)?, 0.006);
+
+        let stale = TipContext { tips: None, ..ctx };
+        assert!(config.tip_value(&stale, None).is_err());
+        assert_close(config.tip_value(&stale, Some(0.001))?, 0.002);
+
+        let config = TipPolicyConfig {
+            kind: TipPolicyKind::Fixed { amount: 0.00001 },
+            floor: 0.0001,
+            ceiling: 0.2,
+        };
+        assert_close(config.tip_value(&ctx, None)?, 0.0001);
+        assert_close(config.clamp(0.3), 0.2);
+        Ok(())
+    }
+}
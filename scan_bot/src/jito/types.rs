@@ -0,0 +1,90 @@
+use serde::Deserialize;
+use serde_json::Value;
+use solana_transaction_status::TransactionConfirmationStatus;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcErrorObject {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+/// JSON-RPC 2.0 envelope, exactly one of `result` and `error` is set.
+#[derive(Debug, Deserialize)]
+pub struct JsonRpcResponse<T> {
+    pub result: Option<T>,
+    pub error: Option<RpcErrorObject>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcContext {
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContextResponse<T> {
+    pub context: RpcContext,
+    pub value: T,
+}
+
+/// Entry of `getBundleStatuses`, only known for landed bundles.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BundleStatus {
+    pub bundle_id: String,
+    pub transactions: Vec<String>, // signatures in bundle order
+    pub slot: u64,
+    pub confirmation_status: Option<TransactionConfirmationStatus>,
+    pub err: Option<Value>, // {"Ok": null} on success
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum InflightStatus {
+    Invalid, // not found, or older than 5 minutes
+    Pending, // not failed, not landed
+    Failed,  // every regional auction failed
+    Landed,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Entry of `getInflightBundleStatuses`, covers the last 5 minutes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InflightBundleStatus {
+    pub bundle_id: String,
+    pub status: InflightStatus,
+    pub landed_slot: Option<u64>,
+}
+
+// unknown bundle ids come back as null
+pub type BundleStatuses = ContextResponse<Vec<Option<BundleStatus>>>;
+pub type InflightBundleStatuses = ContextResponse<Vec<Option<InflightBundleStatus>>>;
+
+/// `summary` of `simulateBundle`: "succeeded" or {"failed": {...}}.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SimulateBundleSummary {
+    Succeeded,
+    Failed {
+        error: Value, // bundle execution error, e.g. {"TransactionFailure": [sig, msg]}
+        #[serde(rename = "txSignature")]
+        tx_signature: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedTransaction {
+    pub err: Option<Value>,
+    pub logs: Option<Vec<String>>,
+    pub units_consumed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateBundleResult {
+    pub summary: SimulateBundleSummary,
+    pub transaction_results: Vec<SimulatedTransaction>, // up to the failed transaction
+}
+
+pub type SimulateBundleResponse = ContextResponse<SimulateBundleResult>;
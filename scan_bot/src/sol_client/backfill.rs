@@ -0,0 +1,247 @@
+use std::{str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+    rpc_response::{RpcConfirmedTransactionStatusWithSignature, RpcLogsResponse},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::{
+    sync::mpsc,
+    time::{interval, Interval, MissedTickBehavior},
+};
+use tracing::{debug, info};
+
+use super::{
+    client::SolanaMonitor,
+    event::{parse_logs_response, PumpEvent, TxEvent},
+};
+
+// the most getSignaturesForAddress returns at once
+const SIGNATURES_PAGE_LIMIT: usize = 1000;
+pub const DEFAULT_BACKFILL_REQUESTS_PER_SECOND: f64 = 10.0;
+
+/// How far a backfill of one bonding curve got. Transactions are delivered
+/// oldest first, so a run resumes with the signatures after `until`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackfillCursor {
+    pub until: Option<String>, // newest signature already delivered
+    pub slot: Option<u64>,     // slot of `until`
+}
+
+// getSignaturesForAddress lists newest first, events go out oldest first
+fn slot_order(
+    mut signatures: Vec<RpcConfirmedTransactionStatusWithSignature>,
+) -> Vec<RpcConfirmedTransactionStatusWithSignature> {
+    signatures.reverse();
+    signatures.sort_by_key(|s| s.slot);
+    signatures
+}
+
+// trades of `mint` only, a transaction may trade other mints as well
+fn trade_events(mint: &str, slot: u64, logs: &RpcLogsResponse) -> Vec<TxEvent> {
+    parse_logs_response(slot, logs)
+        .into_iter()
+        .filter(|e| matches!(&e.event, PumpEvent::Trade(trade) if trade.data.mint == mint))
+        .collect()
+}
+
+fn rate_limit(requests_per_second: f64) -> Interval {
+    let mut pacer = interval(Duration::from_secs_f64(1.0 / requests_per_second.max(0.01)));
+    pacer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    pacer
+}
+
+impl SolanaMonitor {
+    /// Send the trades of `mint` since `cursor`, in slot order, at most
+    /// `requests_per_second` rpc calls a second. The cursor moves after each
+    /// transaction, after an error the same cursor picks up where it stopped.
+    /// When the receiver is dropped partway through a transaction the cursor
+    /// stays before it, so a resumed run sends its earlier trades again.
+    /// Returns the number of trades sent.
+    pub async fn backfill_trades(
+        &self,
+        mint: &str,
+        cursor: &mut BackfillCursor,
+        requests_per_second: f64,
+        sender: &mpsc::Sender<TxEvent>,
+    ) -> Result<usize> {
+        let bonding_curve = self.get_bonding_curve(mint).await?;
+        let until = cursor
+            .until
+            .as_deref()
+            .map(Signature::from_str)
+            .transpose()?;
+        let mut pacer = rate_limit(requests_per_second);
+
+        let signatures = self
+            .list_signatures(bonding_curve, until, &mut pacer)
+            .await?;
+        info!(
+            "backfill {}: {} transactions since {:?}",
+            mint,
+            signatures.len(),
+            cursor.until
+        );
+
+        let mut sent = 0;
+        for status in slot_order(signatures) {
+            // failed transactions emit no events, no need to fetch them
+            if status.err.is_none() {
+                pacer.tick().await;
+                let logs = self.get_transaction_logs(&status.signature).await?;
+                for event in trade_events(mint, status.slot, &logs) {
+                    if sender.send(event).await.is_err() {
+                        return Ok(sent);
+                    }
+                    sent += 1;
+                }
+            }
+            cursor.until = Some(status.signature);
+            cursor.slot = Some(status.slot);
+        }
+        info!("backfill {}: {} trades, cursor: {:?}", mint, sent, cursor);
+        Ok(sent)
+    }
+
+    // every signature of `address` after `until`, newest first
+    async fn list_signatures(
+        &self,
+        address: Pubkey,
+        until: Option<Signature>,
+        pacer: &mut Interval,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let mut signatures = vec![];
+        let mut before = None;
+        loop {
+            pacer.tick().await;
+            let page = self
+                .rpc
+                .call("getSignaturesForAddress", |rpc| async move {
+                    let config = GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until,
+                        limit: Some(SIGNATURES_PAGE_LIMIT),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    };
+                    rpc.get_signatures_for_address_with_config(&address, config)
+                        .await
+                })
+                .await?;
+            debug!("backfill {}: page of {} signatures", address, page.len());
+
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(Signature::from_str(&last.signature)?);
+            let full = page.len() == SIGNATURES_PAGE_LIMIT;
+            signatures.extend(page);
+            if !full {
+                break;
+            }
+        }
+        Ok(signatures)
+    }
+
+    // logs of `signature`, shaped like a logsSubscribe notification
+    async fn get_transaction_logs(&self, signature: &str) -> Result<RpcLogsResponse> {
+        let sig = Signature::from_str(signature)?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let tx = self
+            .rpc
+            .call("getTransaction", |rpc| async move {
+                rpc.get_transaction_with_config(&sig, config).await
+            })
+            .await?;
+        let meta = tx
+            .transaction
+            .meta
+            .ok_or_else(|| anyhow!("transaction {} has no status meta", signature))?;
+        let logs: Option<Vec<String>> = meta.log_messages.into();
+        Ok(RpcLogsResponse {
+            signature: signature.to_string(),
+            err: meta.err,
+            logs: logs.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as base64, Engine};
+
+    use crate::sol_client::event::{
+        COMPLETE_EVENT_DISCRIMINATOR, PROGRAM_DATA_PREFIX, TRADE_PROGRAM_DATA,
+    };
+
+    #[test]
+    fn test_slot_order() {
+        let status = |signature: &str, slot| RpcConfirmedTransactionStatusWithSignature {
+            signature: signature.to_string(),
+            slot,
+            err: None,
+            memo: None,
+            block_time: None,
+            confirmation_status: None,
+        };
+        // as listed: newest first, later transactions of a slot first
+        let listed = vec![
+            status("d", 12),
+            status("c", 11),
+            status("b", 11),
+            status("a", 10),
+        ];
+        assert_eq!(
+            slot_order(listed)
+                .iter()
+                .map(|s| s.signature.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn test_trade_events() {
+        let mut complete = COMPLETE_EVENT_DISCRIMINATOR.to_vec();
+        complete.extend_from_slice(&[0u8; 32 * 3 + 8]);
+        let logs = RpcLogsResponse {
+            signature: "sig".to_string(),
+            err: None,
+            logs: vec![
+                TRADE_PROGRAM_DATA.to_string(),
+                format!("{}{}", PROGRAM_DATA_PREFIX, base64.encode(&complete)),
+            ],
+        };
+
+        let events = trade_events("7R4zU5pgHFxRQaNUhhCAPFXaSN6AWiheD6rRfkFJpump", 7, &logs);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].context.slot, 7);
+        assert_eq!(events[0].event.name(), "TradeEvent");
+        // trades of other mints in the same transaction are dropped
+        let other = Pubkey::new_unique().to_string();
+        assert!(trade_events(&other, 7, &logs).is_empty());
+    }
+
+    #[test]
+    fn test_backfill_cursor_roundtrip() -> Result<()> {
+        let cursor = BackfillCursor {
+            until: Some("sig".to_string()),
+            slot: Some(42),
+        };
+        let json = serde_json::to_string(&cursor)?;
+        assert_eq!(serde_json::from_str::<BackfillCursor>(&json)?, cursor);
+        assert_eq!(
+            serde_json::from_str::<BackfillCursor>("{}")?,
+            BackfillCursor::default()
+        );
+        Ok(())
+    }
+}
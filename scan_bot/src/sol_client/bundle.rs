@@ -0,0 +1,350 @@
+use std::{env, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use serde_json::json;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, bs58, hash::Hash, instruction::Instruction,
+    pubkey::Pubkey, signature::Keypair, signature::Signature, signer::Signer, system_instruction,
+    transaction::VersionedTransaction,
+};
+use spl_token::ui_amount_to_amount;
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+use super::{
+    client::{track_signature, SolanaMonitor},
+    landing::{LandingHandle, LandingStatus, SendResult},
+    lookup_table::build_signed_transaction,
+};
+use crate::{
+    config::get_global_config,
+    jito::{
+        bundle_status::get_bundle_tracker, get_tip_value, simulation::ensure_bundle_succeeds,
+        tip_policy::TipRequest, JITO,
+    },
+};
+
+// block engines reject bundles with more transactions
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+struct BundleTransaction<'a> {
+    payer: &'a Keypair,
+    signers: Vec<&'a Keypair>, // besides the payer
+    instructions: Vec<Instruction>,
+}
+
+/// Several transactions, possibly from different wallets, landing together
+/// or not at all. They share one blockhash and only the last one tips.
+#[derive(Default)]
+pub struct BundleBuilder<'a> {
+    transactions: Vec<BundleTransaction<'a>>,
+    tip_request: TipRequest,
+}
+
+impl<'a> BundleBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a transaction paid and signed by `payer`.
+    pub fn transaction(self, payer: &'a Keypair, instructions: Vec<Instruction>) -> Self {
+        self.transaction_with_signers(payer, &[], instructions)
+    }
+
+    /// Append a transaction paid by `payer` that `signers` sign as well.
+    pub fn transaction_with_signers(
+        mut self,
+        payer: &'a Keypair,
+        signers: &[&'a Keypair],
+        instructions: Vec<Instruction>,
+    ) -> Self {
+        self.transactions.push(BundleTransaction {
+            payer,
+            signers: signers.to_vec(),
+            instructions,
+        });
+        self
+    }
+
+    pub fn tip_request(mut self, tip_request: TipRequest) -> Self {
+        self.tip_request = tip_request;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Sign every transaction against `recent_blockhash`, the last one pays
+    /// `tip_lamports` to `tip_account`.
+    pub fn build(
+        &self,
+        tip_account: &Pubkey,
+        tip_lamports: u64,
+        lookup_tables: Option<&[AddressLookupTableAccount]>,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<VersionedTransaction>> {
+        if self.transactions.is_empty() {
+            return Err(anyhow!("Bundle must contain at least one transaction"));
+        }
+        if self.transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(anyhow!(
+                "Bundle can contain at most {} transactions, got {}",
+                MAX_BUNDLE_TRANSACTIONS,
+                self.transactions.len()
+            ));
+        }
+
+        let last = self.transactions.len() - 1;
+        self.transactions
+            .iter()
+            .enumerate()
+            .map(|(i, tx)| {
+                let payer = tx.payer.pubkey();
+                let mut instructions = tx.instructions.clone();
+                if i == last {
+                    instructions.push(system_instruction::transfer(
+                        &payer,
+                        tip_account,
+                        tip_lamports,
+                    ));
+                }
+
+                let mut signers = vec![tx.payer];
+                for signer in &tx.signers {
+                    if !signers.iter().any(|s| s.pubkey() == signer.pubkey()) {
+                        signers.push(signer);
+                    }
+                }
+                build_signed_transaction(
+                    &payer,
+                    &signers,
+                    &instructions,
+                    lookup_tables,
+                    recent_blockhash,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Tip account and tip in lamports, extra tip included, for `tip_request`.
+async fn jito_tip(jito_client: &JITO, tip_request: TipRequest) -> Result<(Pubkey, u64)> {
+    let c = get_global_config().await;
+    let tip_account = jito_client.get_random_tip_account().await?;
+    let tip = get_tip_value(tip_request).await?;
+    let tip_lamports = ui_amount_to_amount(tip, spl_token::native_mint::DECIMALS);
+    info!(
+        "tip account: {}, urgency: {:?}, extra tip:{} Tip: {} SOL, {} lamports",
+        tip_account, tip_request.urgency, c.jito_config.extra_tip, tip, tip_lamports
+    );
+    Ok((tip_account, tip_lamports))
+}
+
+impl SolanaMonitor {
+    /// Send one tipped transaction through jito `sendTransaction`, tracked
+    /// like an rpc send. `bundle_only` gives it bundle revert protection.
+    pub async fn send_with_jito_transaction(
+        &self,
+        keypair: &Keypair,
+        instructions: Vec<Instruction>,
+        tip_request: TipRequest,
+    ) -> Result<SendResult> {
+        let start_time = Instant::now();
+        let jito_client = JITO::default_client().await;
+        let c = get_global_config().await;
+        let (
+            (tip_account, tip_lamports),
+            (recent_blockhash, last_valid_block_height),
+            lookup_tables,
+        ) = tokio::try_join!(
+            jito_tip(&jito_client, tip_request),
+            self.latest_blockhash(),
+            self.configured_lookup_tables(),
+        )?;
+        let transaction = BundleBuilder::new()
+            .transaction(keypair, instructions)
+            .build(
+                &tip_account,
+                tip_lamports,
+                lookup_tables.as_deref(),
+                recent_blockhash,
+            )?
+            .remove(0);
+
+        let params = json!({
+            "tx": base64.encode(bincode::serialize(&transaction)?),
+            "skipPreflight": true,
+        });
+        let signature = jito_client
+            .client
+            .send_txn(Some(params), c.send_config.jito_bundle_only)
+            .await?;
+        let signature = Signature::from_str(&signature)
+            .map_err(|e| anyhow!("jito: invalid signature {}: {}", signature, e))?;
+
+        debug!(
+            "Send jito transaction signature: {}, cost: {:?}",
+            signature,
+            Instant::now().duration_since(start_time)
+        );
+
+        Ok(SendResult {
+            signatures: vec![signature.to_string()],
+            bundle_id: None,
+            landing: LandingHandle::spawn(track_signature(
+                self.rpc.clone(),
+                signature,
+                last_valid_block_height,
+            )),
+        })
+    }
+
+    /// Tip, sign and send `bundle` through jito.
+    pub async fn send_bundle(&self, bundle: BundleBuilder<'_>) -> Result<SendResult> {
+        let start_time = Instant::now();
+        let jito_client = JITO::default_client().await;
+        let c = get_global_config().await;
+        let ((tip_account, tip_lamports), (recent_blockhash, _), lookup_tables) = tokio::try_join!(
+            jito_tip(&jito_client, bundle.tip_request),
+            self.latest_blockhash(),
+            self.configured_lookup_tables(),
+        )?;
+        let transactions = bundle.build(
+            &tip_account,
+            tip_lamports,
+            lookup_tables.as_deref(),
+            recent_blockhash,
+        )?;
+        let signatures: Vec<String> = transactions
+            .iter()
+            .map(|tx| tx.signatures[0].to_string())
+            .collect();
+
+        // 使用SIMULATE可以查看构建的交易是否正确
+        if env::var("TX_SIMULATE").ok() == Some("true".to_string()) {
+            for transaction in &transactions {
+                let result = self
+                    .rpc
+                    .call("simulateTransaction", |rpc| async move {
+                        rpc.simulate_transaction(transaction).await
+                    })
+                    .await?;
+                if let Some(logs) = result.value.logs {
+                    for log in logs {
+                        info!("Simulate log: {:?}", log);
+                    }
+                }
+            }
+            return Ok(SendResult {
+                landing: LandingHandle::ready(LandingStatus::Failed {
+                    signature: signatures[0].clone(),
+                    error: "simulation only, not sent".to_string(),
+                }),
+                signatures,
+                bundle_id: None,
+            });
+        }
+
+        let serialized_txs = transactions
+            .iter()
+            .map(bincode::serialize)
+            .collect::<Result<Vec<_>, _>>()?;
+        if c.jito_config.simulate_bundle {
+            let simulation_client = JITO::simulation_client().await;
+            let encoded = serialized_txs.iter().map(|tx| base64.encode(tx)).collect();
+            ensure_bundle_succeeds(&simulation_client, encoded).await?;
+        }
+        let bundle = json!(serialized_txs
+            .iter()
+            .map(|tx| bs58::encode(tx).into_string())
+            .collect::<Vec<_>>());
+
+        let submission = jito_client.send_bundle(Some(bundle)).await?;
+        let bundle_id = submission.bundle_id.clone();
+
+        debug!(
+            "Send bundle id: {:?}, accepted by: {:?}, cost: {:?}",
+            bundle_id,
+            submission.accepted_regions(),
+            Instant::now().duration_since(start_time)
+        );
+
+        let tracker = get_bundle_tracker().await;
+        let tracked_bundle_id = bundle_id.clone();
+        let tracked_signature = signatures[0].clone();
+        let landing = LandingHandle::spawn(async move {
+            tracker.wait(&tracked_bundle_id, &tracked_signature).await
+        });
+
+        Ok(SendResult {
+            signatures,
+            bundle_id: Some(bundle_id),
+            landing,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{instruction::AccountMeta, message::VersionedMessage};
+
+    #[test]
+    fn test_bundle_builder() -> Result<()> {
+        let buyer = Keypair::new();
+        let other = Keypair::new();
+        let co_signer = Keypair::new();
+        let tip_account = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+
+        let ix = |signer: &Keypair| {
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![AccountMeta::new(signer.pubkey(), true)],
+            )
+        };
+
+        let bundle = BundleBuilder::new()
+            .transaction(&buyer, vec![ix(&buyer)])
+            .transaction_with_signers(&other, &[&co_signer, &other], vec![ix(&co_signer)]);
+        assert_eq!(bundle.len(), 2);
+
+        let transactions = bundle.build(&tip_account, 1_000, None, blockhash)?;
+        assert_eq!(transactions.len(), 2);
+        for tx in &transactions {
+            assert_eq!(*tx.message.recent_blockhash(), blockhash);
+            assert!(tx.verify_with_results().iter().all(|ok| *ok));
+        }
+        // only the last transaction tips
+        let tips =
+            |tx: &VersionedTransaction| tx.message.static_account_keys().contains(&tip_account);
+        assert!(!tips(&transactions[0]));
+        assert!(tips(&transactions[1]));
+        match &transactions[1].message {
+            VersionedMessage::Legacy(message) => {
+                assert_eq!(message.account_keys[0], other.pubkey());
+                assert_eq!(message.header.num_required_signatures, 2);
+            }
+            _ => panic!("expect legacy message"),
+        }
+
+        assert!(BundleBuilder::new()
+            .build(&tip_account, 1_000, None, blockhash)
+            .is_err());
+        let mut too_many = BundleBuilder::new();
+        for _ in 0..=MAX_BUNDLE_TRANSACTIONS {
+            too_many = too_many.transaction(&buyer, vec![ix(&buyer)]);
+        }
+        assert!(too_many
+            .build(&tip_account, 1_000, None, blockhash)
+            .is_err());
+        Ok(())
+    }
+}
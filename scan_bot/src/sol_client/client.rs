@@ -1,218 +1,153 @@
 use anyhow::{anyhow, Result};
-use borsh::from_slice;
-use futures_util::{SinkExt, StreamExt};
-use serde_json::{json, Value};
 use solana_client::{
-    rpc_client::RpcClient,
     rpc_config::{self},
     rpc_response::{Response, RpcKeyedAccount, RpcLogsResponse, RpcTokenAccountBalance},
 };
 use solana_sdk::{
-    bs58,
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        AddressLookupTableAccount,
+    },
     commitment_config::CommitmentConfig,
+    hash::Hash,
     instruction::Instruction,
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
-    system_instruction,
-    transaction::Transaction,
+    transaction::{Transaction, VersionedTransaction},
 };
 use solana_transaction_status::{UiTransactionEncoding, UiTransactionStatusMeta};
-use spl_token::ui_amount_to_amount;
+use spl_associated_token_account::get_associated_token_address;
 use spl_token_client::{
-    client::{ProgramClient, ProgramRpcClient, ProgramRpcClientSendTransaction},
     spl_token_2022::{extension::StateWithExtensionsOwned, state::Account},
     token::{TokenError, TokenResult},
 };
-use std::{env, str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 use tokio::{
-    sync::{mpsc::Sender, RwLock},
+    sync::{mpsc::Sender, OnceCell, RwLock},
     time::Instant,
 };
-use tokio_tungstenite::{connect_async, tungstenite::Message};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, info, warn};
 
 use crate::{
     config::get_global_config,
-    jito::{get_tip_value, JITO},
+    jito::{tip_policy::TipRequest, JITO},
 };
 
-use super::{get_pda, BondingCurveAccount, PUMP_PROGRAM};
+use super::{
+    bundle::BundleBuilder,
+    get_pda,
+    landing::{decode_transaction_error, LandingHandle, LandingStatus, SendResult},
+    log_race::LogRace,
+    lookup_table::{
+        build_versioned_transaction, decode_lookup_table, get_lookup_table_cache,
+        pump_static_accounts, MAX_EXTEND_ADDRESSES,
+    },
+    priority_fee::{writable_accounts, SendMode},
+    pump_amm::{get_pool_pda, AmmPool, GlobalConfig, PoolState, PUMP_AMM_GLOBAL_CONFIG},
+    rpc_pool::{RpcPool, DEFAULT_RPC_TIMEOUT},
+    subscription::SubscriptionManager,
+    BondingCurveAccount, PUMP_PROGRAM,
+};
+
+// sendAndConfirmTransaction waits for confirmation, far longer than a query
+const SEND_AND_CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+// a blockhash stays valid for ~60s, reusing it this long costs nothing
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(2);
+
+static RECENT_BLOCKHASH: OnceCell<RwLock<Option<RecentBlockhash>>> = OnceCell::const_new();
+
+#[derive(Debug, Clone, Copy)]
+struct RecentBlockhash {
+    hash: Hash,
+    last_valid_block_height: u64,
+    fetched_at: Instant,
+}
+
+async fn get_blockhash_cache() -> &'static RwLock<Option<RecentBlockhash>> {
+    RECENT_BLOCKHASH
+        .get_or_init(|| async { RwLock::new(None) })
+        .await
+}
+
+fn fresh_blockhash(recent: Option<&RecentBlockhash>, now: Instant) -> Option<(Hash, u64)> {
+    recent
+        .filter(|r| now.duration_since(r.fetched_at) <= BLOCKHASH_MAX_AGE)
+        .map(|r| (r.hash, r.last_valid_block_height))
+}
 
 pub struct SolanaMonitor {
-    websocket_url: String,
-    rpc_client: RpcClient,
-    noblocking_rpc_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    websocket_urls: Vec<String>,
+    pub(crate) rpc: Arc<RpcPool>, // every rpc provider, failing over between them
 }
 
 impl SolanaMonitor {
     pub fn new(websocket_url: &str, rpc_url: &str) -> Self {
+        Self::with_providers(
+            &[websocket_url.to_string()],
+            &[rpc_url.to_string()],
+            DEFAULT_RPC_TIMEOUT,
+        )
+    }
+
+    /// Monitor on several providers, the first of each list is the primary.
+    /// Neither list may be empty, config validation makes sure of that.
+    pub fn with_providers(
+        websocket_urls: &[String],
+        rpc_urls: &[String],
+        timeout: Duration,
+    ) -> Self {
         Self {
-            websocket_url: websocket_url.to_string(),
-            rpc_client: RpcClient::new(rpc_url.to_string()),
-            noblocking_rpc_client: Arc::new(
-                solana_client::nonblocking::rpc_client::RpcClient::new(rpc_url.to_string()),
-            ),
+            websocket_urls: websocket_urls.to_vec(),
+            rpc: Arc::new(RpcPool::new(rpc_urls, timeout)),
         }
     }
 
     pub async fn default_client() -> Self {
         let c = get_global_config().await;
-        Self::new(&c.solana_wss_url, &c.solana_rpc_url)
+        Self::with_providers(&c.wss_urls(), &c.rpc_urls(), c.rpc_timeout())
     }
 
-    pub async fn start_program_subscribe(
-        &self,
-        address: &str,
-        sender: Sender<RpcKeyedAccount>,
-    ) -> Result<()> {
+    /// Primary websocket provider.
+    pub fn websocket_url(&self) -> &str {
+        &self.websocket_urls[0]
+    }
+
+    pub async fn start_program_subscribe(&self, address: &str, sender: Sender<RpcKeyedAccount>) {
         info!("Started monitoring program address: {}", address);
-        let sub_msg = json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "programSubscribe",
-            "params": [
-                address,
-                {
-                    "commitment": "confirmed",
-                    "encoding": "jsonParsed"
-                }
-            ]
-        });
-        let (ws_stream, _) = connect_async(&self.websocket_url).await?;
-        let (mut write, mut read) = ws_stream.split();
-
-        // subscribe
-        write.send(Message::text(sub_msg.to_string())).await?;
-        info!("Subscribe program subcribe successfully!");
-
-        while let Some(msg) = read.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    let v: Value = serde_json::from_str(&text)?;
-                    if let Some(params) = v.get("params") {
-                        if let Some(result) = params.get("result") {
-                            if let Ok(info) =
-                                serde_json::from_value::<Response<RpcKeyedAccount>>(result.clone())
-                            {
-                                if let Err(e) = sender.send(info.value.clone()).await {
-                                    error!("Error sending message: {:?}", e);
-                                } else {
-                                    info!(
-                                        "Send message: {:?}, capital: {}",
-                                        info,
-                                        sender.capacity()
-                                    );
-                                }
-                            } else {
-                                debug!("Receive can't parse json message: {:?}", result);
-                            }
-                        } else {
-                            debug!("Receive not result message: {:?}", params);
-                        }
-                    } else {
-                        debug!("Receive not params message: {:?}", v);
-                    }
-                }
-                Ok(_) => {
-                    info!("Receive not text message: {:?}", msg);
-                }
-                Err(e) => {
-                    error!("Error receiving message: {:?}", e);
-                }
-            }
-        }
-        Ok(())
+        SubscriptionManager::program(self.websocket_url(), address)
+            .run_accounts(sender)
+            .await
     }
 
+    /// Forward logs mentioning `address` until the receiver is dropped,
+    /// racing every websocket provider. Use `LogRace` directly to watch the
+    /// connections.
     pub async fn start_log_subscribe(
         &self,
         address: &str,
-        sender: Sender<RpcLogsResponse>,
-        sub_success: Option<Arc<RwLock<bool>>>,
-    ) -> Result<()> {
-        // let (a, b) = PubsubClient::logs_subscribe(url, filter, config).await?;
+        sender: Sender<Response<RpcLogsResponse>>,
+    ) {
         info!("Started monitoring log address: {}", address);
-        // 实现订阅日志
-        let sub_msg = json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "logsSubscribe",
-            "params": [
-                {
-                    "mentions": [address]
-                },
-                {
-                    "commitment": "confirmed"
-                }
-            ]
-        });
-        let (ws_stream, _) = connect_async(&self.websocket_url).await?;
-        let (mut write, mut read) = ws_stream.split();
-
-        if sub_success.is_some() {
-            *sub_success.unwrap().write().await = true;
-        }
-
-        //  subscribe
-        write.send(Message::text(sub_msg.to_string())).await?;
-        info!("Subscribe address: {} logs subcribe successfully!", address);
-
-        while let Some(msg) = read.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    let v: Value = serde_json::from_str(&text)?;
-                    if let Some(params) = v.get("params") {
-                        if let Some(result) = params.get("result") {
-                            if let Ok(log) =
-                                serde_json::from_value::<Response<RpcLogsResponse>>(result.clone())
-                            {
-                                if log.value.err.is_none() {
-                                    if let Err(e) = sender.send(log.value.clone()).await {
-                                        error!("Error sending message: {:?}", e);
-                                    } else {
-                                        debug!(
-                                            "Send message: {:?}, capital: {}",
-                                            log.value,
-                                            sender.capacity()
-                                        );
-                                    }
-                                } else {
-                                    debug!("Error receiving message: {:?}", result);
-                                }
-                            } else {
-                                debug!("Receive can't parse json message: {:?}", result);
-                            }
-                        } else {
-                            debug!("Receive not result message: {:?}", params);
-                        }
-                    } else {
-                        debug!("Receive not params message: {:?}", v);
-                    }
-                }
-                Ok(_) => {
-                    info!("Receive not text message: {:?}", msg);
-                }
-                Err(e) => {
-                    error!("Error receiving message: {:?}", e);
-                }
-            }
-        }
-
-        Ok(())
+        LogRace::new(&self.websocket_urls, address)
+            .run(sender)
+            .await
     }
 
     pub async fn get_tx(&self, sig: &str) -> Result<UiTransactionStatusMeta> {
         // 实现获取交易信息
         let sig = Signature::from_str(sig)?;
-        let tx = self.rpc_client.get_transaction_with_config(
-            &sig,
-            rpc_config::RpcTransactionConfig {
-                encoding: Some(UiTransactionEncoding::Json),
-                commitment: Some(CommitmentConfig::confirmed()),
-                max_supported_transaction_version: None,
-            },
-        )?;
+        let config = rpc_config::RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: None,
+        };
+        let tx = self
+            .rpc
+            .call("getTransaction", |rpc| async move {
+                rpc.get_transaction_with_config(&sig, config).await
+            })
+            .await?;
         if let Some(meta) = tx.transaction.meta {
             if let Some(e) = meta.err {
                 anyhow::bail!("Transaction error: {:?}", e);
@@ -225,8 +160,12 @@ impl SolanaMonitor {
     pub async fn get_largest_accounts(&self, address: &str) -> Result<Vec<RpcTokenAccountBalance>> {
         let mint = Pubkey::from_str(address)?;
         let res = self
-            .rpc_client
-            .get_token_largest_accounts_with_commitment(&mint, CommitmentConfig::confirmed())?;
+            .rpc
+            .call("getTokenLargestAccounts", |rpc| async move {
+                rpc.get_token_largest_accounts_with_commitment(&mint, CommitmentConfig::confirmed())
+                    .await
+            })
+            .await?;
 
         Ok(res.value)
     }
@@ -240,8 +179,11 @@ impl SolanaMonitor {
         // todo query associated bonding curve
         // get bonding curve data
         let bonding_curve_data = self
-            .rpc_client
-            .get_account_data(&bonding_curve)
+            .rpc
+            .call("getAccountInfo", |rpc| async move {
+                rpc.get_account_data(&bonding_curve).await
+            })
+            .await
             .inspect_err(|err| {
                 warn!(
                     "Failed to get bonding curve account data address: {}, bonding curve: {}, err: {}",
@@ -249,16 +191,63 @@ impl SolanaMonitor {
                 );
             })?;
 
-        let bonding_curve_account = from_slice::<BondingCurveAccount>(&bonding_curve_data)
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to decode bonding curve account data, err: {}",
-                    e.to_string()
-                )
-            })?;
+        let bonding_curve_account = BondingCurveAccount::decode(&bonding_curve_data)?;
         Ok((bonding_curve, bonding_curve_account))
     }
 
+    /// Pump swap pool a completed bonding curve of `address` migrated to,
+    /// with the current pool reserves.
+    pub async fn get_amm_pool(&self, address: &str) -> Result<AmmPool> {
+        let mint = Pubkey::from_str(address)?;
+        let pool = get_pool_pda(&mint);
+        let global_config = Pubkey::from_str_const(PUMP_AMM_GLOBAL_CONFIG);
+
+        let accounts = self
+            .rpc
+            .call("getMultipleAccounts", |rpc| async move {
+                rpc.get_multiple_accounts(&[pool, global_config]).await
+            })
+            .await
+            .inspect_err(|err| {
+                warn!(
+                    "Failed to get amm pool address: {}, pool: {}, err: {}",
+                    address, pool, err
+                );
+            })?;
+        let pool_account = accounts[0]
+            .as_ref()
+            .ok_or_else(|| anyhow!("amm pool not found, address: {}, pool: {}", address, pool))?;
+        let global_config_account = accounts[1]
+            .as_ref()
+            .ok_or_else(|| anyhow!("amm global config not found"))?;
+
+        let state = PoolState::decode(&pool_account.data)?;
+        let global_config = GlobalConfig::decode(&global_config_account.data)?;
+
+        let (base_reserves, quote_reserves) = tokio::try_join!(
+            self.get_token_account_amount(&state.pool_base_token_account),
+            self.get_token_account_amount(&state.pool_quote_token_account),
+        )?;
+
+        Ok(AmmPool {
+            address: pool,
+            state,
+            global_config,
+            base_reserves,
+            quote_reserves,
+        })
+    }
+
+    async fn get_token_account_amount(&self, account: &Pubkey) -> Result<u64> {
+        let balance = self
+            .rpc
+            .call("getTokenAccountBalance", |rpc| async move {
+                rpc.get_token_account_balance(account).await
+            })
+            .await?;
+        Ok(balance.amount.parse::<u64>()?)
+    }
+
     pub async fn get_bonding_curve(&self, address: &str) -> Result<Pubkey> {
         // 实现获取bonding curve账户信息
         let bonding_curve = get_pda(address, PUMP_PROGRAM)?;
@@ -270,15 +259,15 @@ impl SolanaMonitor {
         address: &Pubkey,
         account: &Pubkey,
     ) -> TokenResult<StateWithExtensionsOwned<Account>> {
-        let program_client = Arc::new(ProgramRpcClient::new(
-            self.noblocking_rpc_client.clone(),
-            ProgramRpcClientSendTransaction,
-        ));
-
-        let account = program_client
-            .get_account(*account)
+        let account = self
+            .rpc
+            .call("getAccountInfo", |rpc| async move {
+                rpc.get_account_with_commitment(account, rpc.commitment())
+                    .await
+            })
             .await
-            .map_err(TokenError::Client)?
+            .map_err(|e| TokenError::Client(e.into()))?
+            .value
             .ok_or(TokenError::AccountNotFound)
             .inspect_err(|err| {
                 warn!(
@@ -298,66 +287,421 @@ impl SolanaMonitor {
         Ok(account)
     }
 
-    pub async fn new_signed_and_send(
+    /// Token balance of `owner`'s associated token account for `mint`,
+    /// 0 when the account doesn't exist.
+    pub async fn get_token_balance(&self, mint: &Pubkey, owner: &Pubkey) -> Result<u64> {
+        let ata = get_associated_token_address(owner, mint);
+        match self.get_account_info(mint, &ata).await {
+            Ok(account) => Ok(account.base.amount),
+            Err(TokenError::AccountNotFound) => {
+                debug!("Token account not found, mint: {}, ata: {}", mint, ata);
+                Ok(0)
+            }
+            Err(e) => Err(anyhow!(
+                "Failed to get token balance, mint: {}, ata: {}, err: {}",
+                mint,
+                ata,
+                e
+            )),
+        }
+    }
+
+    /// Sign `instructions` as configured in `send_config`: a legacy
+    /// transaction, or a v0 one compiled against the configured lookup tables.
+    pub async fn build_transaction(
         &self,
         keypair: &Keypair,
-        mut instructions: Vec<Instruction>,
-    ) -> Result<Vec<String>> {
-        let start_time = Instant::now();
-        let jito_client = JITO::default_client().await;
-        let tip_account = jito_client.get_random_tip_account().await?;
+        instructions: &[Instruction],
+        recent_blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        let lookup_tables = self.configured_lookup_tables().await?;
+        build_versioned_transaction(
+            keypair,
+            instructions,
+            lookup_tables.as_deref(),
+            recent_blockhash,
+        )
+    }
+
+    /// Lookup tables of `send_config`, None when sending legacy transactions.
+    pub async fn configured_lookup_tables(&self) -> Result<Option<Vec<AddressLookupTableAccount>>> {
         let c = get_global_config().await;
-        let mut tip = get_tip_value().await?;
-        tip = tip.min(0.2);
-        tip += c.jito_config.extra_tip;
-        let tip_lamports = ui_amount_to_amount(tip, spl_token::native_mint::DECIMALS);
-        info!(
-            "tip account: {}, extra tip:{} Tip: {} SOL, {} lamports",
-            tip_account, c.jito_config.extra_tip, tip, tip_lamports
-        );
+        if !c.send_config.versioned_transaction {
+            return Ok(None);
+        }
+
+        let keys = c
+            .send_config
+            .lookup_tables
+            .iter()
+            .map(|k| Pubkey::from_str(k))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(self.get_lookup_tables(&keys).await?))
+    }
+
+    /// Lookup tables by address, served from the cache after the first read.
+    pub async fn get_lookup_tables(
+        &self,
+        keys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        let cache = get_lookup_table_cache().await;
+        let missing = {
+            let cache = cache.read().await;
+            keys.iter()
+                .filter(|k| !cache.contains_key(k))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+
+        if !missing.is_empty() {
+            let missing = &missing;
+            let accounts = self
+                .rpc
+                .call("getMultipleAccounts", |rpc| async move {
+                    rpc.get_multiple_accounts(missing).await
+                })
+                .await?;
+            let mut cache = cache.write().await;
+            for (key, account) in missing.iter().zip(accounts) {
+                let account = account.ok_or_else(|| anyhow!("lookup table not found: {}", key))?;
+                let table = decode_lookup_table(*key, &account.data)?;
+                info!(
+                    "Cache lookup table: {}, addresses: {}",
+                    key,
+                    table.addresses.len()
+                );
+                cache.insert(*key, table);
+            }
+        }
+
+        let cache = cache.read().await;
+        Ok(keys.iter().filter_map(|k| cache.get(k).cloned()).collect())
+    }
+
+    /// Create a lookup table owned by `payer` and fill it with the static pump
+    /// accounts. The table is usable from the slot after the last extend.
+    pub async fn create_lookup_table(&self, payer: &Keypair) -> Result<Pubkey> {
+        let recent_slot = self
+            .rpc
+            .call("getSlot", |rpc| async move {
+                rpc.get_slot_with_commitment(CommitmentConfig::finalized())
+                    .await
+            })
+            .await?;
+        let (create_instruction, table) =
+            create_lookup_table(payer.pubkey(), payer.pubkey(), recent_slot);
+
+        let signature = self.send_and_confirm(payer, &[create_instruction]).await?;
+        info!("Create lookup table: {}, signature: {}", table, signature);
+
+        self.extend_lookup_table(payer, &table, pump_static_accounts())
+            .await?;
+        Ok(table)
+    }
+
+    /// Append `addresses` to `table`, skipping those already in it.
+    pub async fn extend_lookup_table(
+        &self,
+        payer: &Keypair,
+        table: &Pubkey,
+        addresses: Vec<Pubkey>,
+    ) -> Result<Vec<Signature>> {
+        // only a missing table counts as empty, any other error would
+        // re-append addresses it already holds
+        let existing = match self
+            .rpc
+            .call("getAccountInfo", |rpc| async move {
+                rpc.get_account_with_commitment(table, rpc.commitment())
+                    .await
+            })
+            .await?
+            .value
+        {
+            Some(account) => decode_lookup_table(*table, &account.data)?.addresses,
+            None => vec![],
+        };
+        let mut new_addresses = vec![];
+        for address in addresses {
+            if !existing.contains(&address) && !new_addresses.contains(&address) {
+                new_addresses.push(address);
+            }
+        }
 
-        // send init tx
-        instructions.push(system_instruction::transfer(
-            &keypair.pubkey(),
-            &tip_account,
-            tip_lamports,
-        ));
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
-
-        // let mut transaction = Transaction::new_with_payer(&instructions, Some(&keypair.pubkey()));
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&keypair.pubkey()),
-            &vec![&*keypair],
+        let mut signatures = vec![];
+        for chunk in new_addresses.chunks(MAX_EXTEND_ADDRESSES) {
+            let instruction =
+                extend_lookup_table(*table, payer.pubkey(), Some(payer.pubkey()), chunk.to_vec());
+            let signature = self.send_and_confirm(payer, &[instruction]).await?;
+            info!(
+                "Extend lookup table: {}, addresses: {}, signature: {}",
+                table,
+                chunk.len(),
+                signature
+            );
+            signatures.push(signature);
+        }
+
+        // addresses changed, reload on next use
+        get_lookup_table_cache().await.write().await.remove(table);
+        Ok(signatures)
+    }
+
+    async fn send_and_confirm(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+    ) -> Result<Signature> {
+        let recent_blockhash = self
+            .rpc
+            .call("getLatestBlockhash", |rpc| async move {
+                rpc.get_latest_blockhash().await
+            })
+            .await?;
+        let transaction = &Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
             recent_blockhash,
         );
+        self.rpc
+            .call_with_timeout(
+                "sendAndConfirmTransaction",
+                SEND_AND_CONFIRM_TIMEOUT,
+                |rpc| async move { rpc.send_and_confirm_transaction(transaction).await },
+            )
+            .await
+    }
 
-        // 使用SIMULATE可以查看构建的交易是否正确
-        if env::var("TX_SIMULATE").ok() == Some("true".to_string()) {
-            let result = self.rpc_client.simulate_transaction(&transaction.clone())?;
-            if let Some(logs) = result.value.logs {
-                for log in logs {
-                    info!("Simulate log: {:?}", log);
-                }
+    /// Confirmed blockhash and the last block height it is valid at. Reused
+    /// for `BLOCKHASH_MAX_AGE`, so it can be fetched ahead of a send.
+    pub async fn latest_blockhash(&self) -> Result<(Hash, u64)> {
+        let cache = get_blockhash_cache().await;
+        if let Some(blockhash) = fresh_blockhash(cache.read().await.as_ref(), Instant::now()) {
+            return Ok(blockhash);
+        }
+
+        let (hash, last_valid_block_height) = self
+            .rpc
+            .call("getLatestBlockhash", |rpc| async move {
+                rpc.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                    .await
+            })
+            .await?;
+        cache.write().await.replace(RecentBlockhash {
+            hash,
+            last_valid_block_height,
+            fetched_at: Instant::now(),
+        });
+        Ok((hash, last_valid_block_height))
+    }
+
+    /// Fetch what a send needs regardless of the trade, the blockhash, jito
+    /// tip accounts and lookup tables, so it overlaps with reading the curve.
+    pub async fn prefetch_send_state(&self) -> Result<()> {
+        let c = get_global_config().await;
+        match c.send_config.mode {
+            SendMode::Rpc => {
+                tokio::try_join!(self.latest_blockhash(), self.configured_lookup_tables())?;
+            }
+            SendMode::Jito | SendMode::JitoTransaction => {
+                let jito_client = JITO::default_client().await;
+                tokio::try_join!(
+                    self.latest_blockhash(),
+                    self.configured_lookup_tables(),
+                    jito_client.get_random_tip_account(),
+                )?;
             }
-            return Ok(vec![]);
         }
+        Ok(())
+    }
 
-        let serialized_tx = bs58::encode(bincode::serialize(&transaction)?).into_string();
-        let bundle = json!([serialized_tx]);
+    /// Recent prioritization fees (micro lamports per cu) paid by transactions
+    /// locking any of `accounts`.
+    pub async fn get_recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>> {
+        let fees = self
+            .rpc
+            .call("getRecentPrioritizationFees", |rpc| async move {
+                rpc.get_recent_prioritization_fees(accounts).await
+            })
+            .await?;
+        Ok(fees.into_iter().map(|f| f.prioritization_fee).collect())
+    }
 
-        let bundle_id = jito_client.send_bundle(Some(bundle), None).await?;
+    /// Sign and send `instructions`, `tip_request` prices the jito tip.
+    pub async fn new_signed_and_send(
+        &self,
+        keypair: &Keypair,
+        instructions: Vec<Instruction>,
+        tip_request: TipRequest,
+    ) -> Result<SendResult> {
+        let c = get_global_config().await;
+        match c.send_config.mode {
+            SendMode::Jito => {
+                self.send_with_jito(keypair, instructions, tip_request)
+                    .await
+            }
+            SendMode::JitoTransaction => {
+                self.send_with_jito_transaction(keypair, instructions, tip_request)
+                    .await
+            }
+            SendMode::Rpc => self.send_with_priority_fee(keypair, instructions).await,
+        }
+    }
+
+    /// Send through `sendTransaction`, paying a priority fee picked from
+    /// recent prioritization fees instead of a jito tip.
+    pub async fn send_with_priority_fee(
+        &self,
+        keypair: &Keypair,
+        instructions: Vec<Instruction>,
+    ) -> Result<SendResult> {
+        let start_time = Instant::now();
+        let c = get_global_config().await;
+        let send_config = &c.send_config;
+
+        let accounts = writable_accounts(&instructions);
+        let (recent_fees, blockhash) = tokio::join!(
+            self.get_recent_prioritization_fees(&accounts),
+            self.latest_blockhash()
+        );
+        let (recent_blockhash, last_valid_block_height) = blockhash?;
+        let recent_fees = recent_fees
+            .inspect_err(|err| {
+                warn!("Failed to get recent prioritization fees, err: {}", err);
+            })
+            .unwrap_or_default();
+        let compute_unit_price = send_config.compute_unit_price(&recent_fees);
+        info!(
+            "compute unit limit: {}, compute unit price: {} micro lamports, from {} recent fees",
+            send_config.compute_unit_limit,
+            compute_unit_price,
+            recent_fees.len()
+        );
+
+        let mut all_instructions = send_config.compute_budget_instructions(compute_unit_price);
+        all_instructions.extend(instructions);
+
+        let transaction = &self
+            .build_transaction(keypair, &all_instructions, recent_blockhash)
+            .await?;
+
+        let signature = self
+            .rpc
+            .call("sendTransaction", |rpc| async move {
+                rpc.send_transaction(transaction).await
+            })
+            .await?;
 
         debug!(
-            "Send bundle id: {:?}, cost: {:?}",
-            bundle_id,
+            "Send transaction signature: {}, cost: {:?}",
+            signature,
             Instant::now().duration_since(start_time)
         );
 
-        // todo check bundle status
+        Ok(SendResult {
+            signatures: vec![signature.to_string()],
+            bundle_id: None,
+            landing: LandingHandle::spawn(track_signature(
+                self.rpc.clone(),
+                signature,
+                last_valid_block_height,
+            )),
+        })
+    }
 
-        // jito_client.check_bundle_status(&bundle_id).await?;
+    /// Send `instructions` as a one-transaction bundle with a tip.
+    pub async fn send_with_jito(
+        &self,
+        keypair: &Keypair,
+        instructions: Vec<Instruction>,
+        tip_request: TipRequest,
+    ) -> Result<SendResult> {
+        let bundle = BundleBuilder::new()
+            .transaction(keypair, instructions)
+            .tip_request(tip_request);
+        self.send_bundle(bundle).await
+    }
+}
+
+/// Poll `signature` until it is confirmed, fails, or its blockhash expires.
+pub(super) async fn track_signature(
+    rpc: Arc<RpcPool>,
+    signature: Signature,
+    last_valid_block_height: u64,
+) -> LandingStatus {
+    loop {
+        match rpc
+            .call("getSignatureStatuses", |rpc| async move {
+                rpc.get_signature_statuses(&[signature]).await
+            })
+            .await
+        {
+            Ok(response) => {
+                if let Some(Some(status)) = response.value.first() {
+                    if let Some(err) = &status.err {
+                        let error = decode_transaction_error(err);
+                        warn!(
+                            "Transaction failed, signature: {}, err: {}",
+                            signature, error
+                        );
+                        return LandingStatus::Failed {
+                            signature: signature.to_string(),
+                            error,
+                        };
+                    }
+                    if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                        info!(
+                            "Transaction landed, signature: {}, slot: {}",
+                            signature, status.slot
+                        );
+                        return LandingStatus::Landed {
+                            signature: signature.to_string(),
+                            slot: status.slot,
+                        };
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to get signature status: {}, err: {}", signature, e);
+            }
+        }
 
-        Ok(vec![])
+        match rpc
+            .call("getBlockHeight", |rpc| async move {
+                rpc.get_block_height().await
+            })
+            .await
+        {
+            Ok(block_height) if block_height > last_valid_block_height => {
+                info!("Transaction expired, signature: {}", signature);
+                return LandingStatus::Expired;
+            }
+            _ => {}
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_blockhash() {
+        let now = Instant::now();
+        let recent = RecentBlockhash {
+            hash: Hash::new_unique(),
+            last_valid_block_height: 100,
+            fetched_at: now,
+        };
+        assert_eq!(
+            fresh_blockhash(Some(&recent), now + BLOCKHASH_MAX_AGE),
+            Some((recent.hash, 100))
+        );
+        assert_eq!(
+            fresh_blockhash(Some(&recent), now + BLOCKHASH_MAX_AGE * 2),
+            None
+        );
+        assert_eq!(fresh_blockhash(None, now), None);
     }
 }
@@ -0,0 +1,263 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use solana_account_decoder::UiAccount;
+use solana_client::rpc_response::Response;
+use solana_sdk::pubkey::Pubkey;
+use tokio::{
+    sync::{broadcast, mpsc, Mutex, RwLock},
+    task::JoinHandle,
+};
+use tracing::{debug, info, warn};
+
+use super::{
+    client::SolanaMonitor, get_pda, subscription::SubscriptionManager, BondingCurveAccount,
+    PUMP_PROGRAM,
+};
+
+const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+const ACCOUNT_CHANNEL_CAPACITY: usize = 64;
+
+/// New state of a watched bonding curve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurveUpdate {
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub slot: u64,
+    pub account: BondingCurveAccount,
+}
+
+impl CurveUpdate {
+    pub fn price(&self) -> f64 {
+        self.account.get_price()
+    }
+}
+
+fn decode_curve_update(
+    mint: Pubkey,
+    bonding_curve: Pubkey,
+    response: &Response<UiAccount>,
+) -> Result<CurveUpdate> {
+    let data = response
+        .value
+        .data
+        .decode()
+        .ok_or_else(|| anyhow!("bonding curve {}: undecodable account data", bonding_curve))?;
+    Ok(CurveUpdate {
+        mint,
+        bonding_curve,
+        slot: response.context.slot,
+        account: BondingCurveAccount::decode(&data)?,
+    })
+}
+
+/// Live bonding curve state of the watched mints, one `accountSubscribe`
+/// each. Mints are added and removed at runtime as positions open and close,
+/// every update is broadcast and the latest one kept.
+pub struct CurveWatcher {
+    websocket_url: String,
+    watched: Mutex<HashMap<Pubkey, JoinHandle<()>>>,
+    latest: Arc<RwLock<HashMap<Pubkey, CurveUpdate>>>,
+    updates: broadcast::Sender<CurveUpdate>,
+}
+
+impl CurveWatcher {
+    pub fn new(websocket_url: &str) -> Self {
+        let (updates, _) = broadcast::channel(UPDATE_CHANNEL_CAPACITY);
+        Self {
+            websocket_url: websocket_url.to_string(),
+            watched: Mutex::new(HashMap::new()),
+            latest: Arc::new(RwLock::new(HashMap::new())),
+            updates,
+        }
+    }
+
+    /// Start watching the bonding curve of `mint`, a no-op when already watched.
+    pub async fn watch(&self, mint: &str) -> Result<()> {
+        let bonding_curve = get_pda(mint, PUMP_PROGRAM)?;
+        let mint = Pubkey::from_str(mint)?;
+        let mut watched = self.watched.lock().await;
+        if watched.contains_key(&mint) {
+            return Ok(());
+        }
+
+        info!("watch bonding curve: {}, mint: {}", bonding_curve, mint);
+        let subscription =
+            SubscriptionManager::account(&self.websocket_url, &bonding_curve.to_string());
+        let latest = self.latest.clone();
+        let updates = self.updates.clone();
+        let handle = tokio::spawn(async move {
+            let (sender, mut receiver) = mpsc::channel(ACCOUNT_CHANNEL_CAPACITY);
+            let forward = async move {
+                while let Some(response) = receiver.recv().await {
+                    match decode_curve_update(mint, bonding_curve, &response) {
+                        Ok(update) => publish(&latest, &updates, update).await,
+                        Err(e) => warn!("watch bonding curve {}: {}", bonding_curve, e),
+                    }
+                }
+            };
+            tokio::join!(subscription.run(sender, Some), forward);
+        });
+        watched.insert(mint, handle);
+        Ok(())
+    }
+
+    /// Stop watching `mint`, returns whether it was watched.
+    pub async fn unwatch(&self, mint: &Pubkey) -> bool {
+        let Some(handle) = self.watched.lock().await.remove(mint) else {
+            return false;
+        };
+        info!("unwatch bonding curve of mint: {}", mint);
+        handle.abort();
+        self.latest.write().await.remove(mint);
+        true
+    }
+
+    pub async fn watched(&self) -> Vec<Pubkey> {
+        self.watched.lock().await.keys().copied().collect()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<CurveUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// Latest known state of `mint`, None until the first update.
+    pub async fn latest(&self, mint: &Pubkey) -> Option<CurveUpdate> {
+        self.latest.read().await.get(mint).cloned()
+    }
+}
+
+impl Drop for CurveWatcher {
+    fn drop(&mut self) {
+        for handle in self.watched.get_mut().values() {
+            handle.abort();
+        }
+    }
+}
+
+// keep the newest state, a replay after a reconnect may be older
+async fn publish(
+    latest: &RwLock<HashMap<Pubkey, CurveUpdate>>,
+    updates: &broadcast::Sender<CurveUpdate>,
+    update: CurveUpdate,
+) {
+    {
+        let mut latest = latest.write().await;
+        if latest
+            .get(&update.mint)
+            .is_some_and(|known| known.slot > update.slot)
+        {
+            debug!("skip stale bonding curve update: {:?}", update);
+            return;
+        }
+        latest.insert(update.mint, update.clone());
+    }
+    // no subscribers is fine
+    let _ = updates.send(update);
+}
+
+impl SolanaMonitor {
+    /// Watcher of bonding curves on the primary websocket provider.
+    pub fn curve_watcher(&self) -> CurveWatcher {
+        CurveWatcher::new(self.websocket_url())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as base64, Engine};
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_curve_update() -> Result<()> {
+        let account = BondingCurveAccount {
+            discriminator: 6966180631402821399,
+            virtual_token_reserves: 1_000_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+        let data = base64.encode(borsh::to_vec(&account)?);
+        let response: Response<UiAccount> = serde_json::from_value(json!({
+            "context": {"slot": 100},
+            "value": {
+                "data": [data, "base64"],
+                "executable": false,
+                "lamports": 1_000_000,
+                "owner": PUMP_PROGRAM,
+                "rentEpoch": 0,
+                "space": 49
+            }
+        }))?;
+
+        let mint = Pubkey::new_unique();
+        let bonding_curve = Pubkey::new_unique();
+        let update = decode_curve_update(mint, bonding_curve, &response)?;
+        assert_eq!(update.slot, 100);
+        assert_eq!(update.account, account);
+        assert!((update.price() - 0.00000003).abs() < 1e-15);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_curve_update_trailing_bytes() -> Result<()> {
+        let account = BondingCurveAccount {
+            discriminator: 6966180631402821399,
+            virtual_token_reserves: 1_000_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+        // creator and padding of a live account
+        let mut data = borsh::to_vec(&account)?;
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.resize(150, 0);
+        let response: Response<UiAccount> = serde_json::from_value(json!({
+            "context": {"slot": 101},
+            "value": {
+                "data": [base64.encode(&data), "base64"],
+                "executable": false,
+                "lamports": 1_000_000,
+                "owner": PUMP_PROGRAM,
+                "rentEpoch": 0,
+                "space": 150
+            }
+        }))?;
+
+        let update = decode_curve_update(Pubkey::new_unique(), Pubkey::new_unique(), &response)?;
+        assert_eq!(update.slot, 101);
+        assert_eq!(update.account, account);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_publish_keeps_newest() {
+        let latest = RwLock::new(HashMap::new());
+        let (updates, mut receiver) = broadcast::channel(8);
+        let update = |slot| CurveUpdate {
+            mint: Pubkey::default(),
+            bonding_curve: Pubkey::default(),
+            slot,
+            account: BondingCurveAccount {
+                discriminator: 0,
+                virtual_token_reserves: 1,
+                virtual_sol_reserves: 1,
+                real_token_reserves: 0,
+                real_sol_reserves: 0,
+                token_total_supply: 0,
+                complete: false,
+            },
+        };
+
+        publish(&latest, &updates, update(10)).await;
+        publish(&latest, &updates, update(9)).await;
+        assert_eq!(receiver.recv().await.unwrap().slot, 10);
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(latest.read().await[&Pubkey::default()].slot, 10);
+    }
+}
@@ -0,0 +1,411 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcLogsResponse;
+use solana_sdk::pubkey::Pubkey;
+use tracing::debug;
+
+use super::{TradeEvent, TradeEventData};
+
+pub const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+// anchor event discriminator: sha256("event:<EventName>")[..8]
+pub const CREATE_EVENT_DISCRIMINATOR: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
+pub const TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+pub const COMPLETE_EVENT_DISCRIMINATOR: [u8; 8] = [95, 114, 97, 156, 212, 46, 152, 8];
+pub const SET_PARAMS_EVENT_DISCRIMINATOR: [u8; 8] = [223, 195, 159, 246, 62, 48, 143, 131];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateEventData {
+    pub name: String,          // token name
+    pub symbol: String,        // token symbol
+    pub uri: String,           // metadata uri
+    pub mint: String,          // token mint address
+    pub bonding_curve: String, // bonding curve address
+    pub user: String,          // creator address
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteEventData {
+    pub user: String,          // user who bought the last token
+    pub mint: String,          // token mint address
+    pub bonding_curve: String, // bonding curve address
+    pub timestamp: i64,        // timestamp
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetParamsEventData {
+    pub fee_recipient: String,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PumpEvent {
+    Create(CreateEventData),
+    Trade(TradeEvent),
+    Complete(CompleteEventData),
+    SetParams(SetParamsEventData),
+}
+
+impl PumpEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PumpEvent::Create(_) => "CreateEvent",
+            PumpEvent::Trade(_) => "TradeEvent",
+            PumpEvent::Complete(_) => "CompleteEvent",
+            PumpEvent::SetParams(_) => "SetParamsEvent",
+        }
+    }
+
+    pub fn mint(&self) -> Option<&str> {
+        match self {
+            PumpEvent::Create(e) => Some(&e.mint),
+            PumpEvent::Trade(e) => Some(&e.data.mint),
+            PumpEvent::Complete(e) => Some(&e.mint),
+            PumpEvent::SetParams(_) => None,
+        }
+    }
+}
+
+/// Where an event came from: the transaction, its slot and the index of the
+/// `Program data` log line inside that transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EventContext {
+    pub signature: String, // transaction signature
+    pub slot: u64,         // slot of the notification
+    pub log_index: usize,  // index of the log line in the transaction
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxEvent<T = PumpEvent> {
+    pub context: EventContext,
+    pub event: T,
+}
+
+impl<T> TxEvent<T> {
+    pub fn new(context: EventContext, event: T) -> Self {
+        Self { context, event }
+    }
+}
+
+// raw on-chain layouts, fields in borsh order. trailing bytes are ignored so
+// fields appended by later program versions don't break decoding.
+#[derive(BorshDeserialize)]
+struct RawCreateEvent {
+    name: String,
+    symbol: String,
+    uri: String,
+    mint: [u8; 32],
+    bonding_curve: [u8; 32],
+    user: [u8; 32],
+}
+
+#[derive(BorshDeserialize)]
+struct RawTradeEvent {
+    mint: [u8; 32],
+    sol_amount: u64,
+    token_amount: u64,
+    is_buy: bool,
+    user: [u8; 32],
+    timestamp: i64,
+    virtual_sol_reserves: u64,
+    virtual_token_reserves: u64,
+    real_sol_reserves: u64,
+    real_token_reserves: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct RawCompleteEvent {
+    user: [u8; 32],
+    mint: [u8; 32],
+    bonding_curve: [u8; 32],
+    timestamp: i64,
+}
+
+#[derive(BorshDeserialize)]
+struct RawSetParamsEvent {
+    fee_recipient: [u8; 32],
+    initial_virtual_token_reserves: u64,
+    initial_virtual_sol_reserves: u64,
+    initial_real_token_reserves: u64,
+    token_total_supply: u64,
+    fee_basis_points: u64,
+}
+
+fn pubkey_string(bytes: [u8; 32]) -> String {
+    Pubkey::new_from_array(bytes).to_string()
+}
+
+fn deserialize<T: BorshDeserialize>(name: &str, mut data: &[u8]) -> Result<T> {
+    T::deserialize(&mut data).map_err(|e| anyhow!("Failed to decode {}: {}", name, e))
+}
+
+/// Decode a pump.fun anchor event from its raw bytes (discriminator included).
+pub fn decode_pump_event(data: &[u8]) -> Result<PumpEvent> {
+    if data.len() < 8 {
+        return Err(anyhow!("Invalid pump event length: {}", data.len()));
+    }
+    let (discriminator, body) = data.split_at(8);
+
+    match <[u8; 8]>::try_from(discriminator)? {
+        CREATE_EVENT_DISCRIMINATOR => {
+            let raw: RawCreateEvent = deserialize("CreateEvent", body)?;
+            Ok(PumpEvent::Create(CreateEventData {
+                name: raw.name,
+                symbol: raw.symbol,
+                uri: raw.uri,
+                mint: pubkey_string(raw.mint),
+                bonding_curve: pubkey_string(raw.bonding_curve),
+                user: pubkey_string(raw.user),
+            }))
+        }
+        TRADE_EVENT_DISCRIMINATOR => {
+            let raw: RawTradeEvent = deserialize("TradeEvent", body)?;
+            Ok(PumpEvent::Trade(TradeEvent {
+                name: "TradeEvent".to_string(),
+                data: TradeEventData {
+                    mint: pubkey_string(raw.mint),
+                    sol_amount: raw.sol_amount,
+                    token_amount: raw.token_amount,
+                    is_buy: raw.is_buy,
+                    user: pubkey_string(raw.user),
+                    timestamp: raw.timestamp,
+                    virtual_sol_reserves: raw.virtual_sol_reserves,
+                    virtual_token_reserves: raw.virtual_token_reserves,
+                    real_sol_reserves: raw.real_sol_reserves,
+                    real_token_reserves: raw.real_token_reserves,
+                },
+            }))
+        }
+        COMPLETE_EVENT_DISCRIMINATOR => {
+            let raw: RawCompleteEvent = deserialize("CompleteEvent", body)?;
+            Ok(PumpEvent::Complete(CompleteEventData {
+                user: pubkey_string(raw.user),
+                mint: pubkey_string(raw.mint),
+                bonding_curve: pubkey_string(raw.bonding_curve),
+                timestamp: raw.timestamp,
+            }))
+        }
+        SET_PARAMS_EVENT_DISCRIMINATOR => {
+            let raw: RawSetParamsEvent = deserialize("SetParamsEvent", body)?;
+            Ok(PumpEvent::SetParams(SetParamsEventData {
+                fee_recipient: pubkey_string(raw.fee_recipient),
+                initial_virtual_token_reserves: raw.initial_virtual_token_reserves,
+                initial_virtual_sol_reserves: raw.initial_virtual_sol_reserves,
+                initial_real_token_reserves: raw.initial_real_token_reserves,
+                token_total_supply: raw.token_total_supply,
+                fee_basis_points: raw.fee_basis_points,
+            }))
+        }
+        unknown => Err(anyhow!(
+            "Unknown pump event discriminator: {}",
+            hex::encode(unknown)
+        )),
+    }
+}
+
+/// Decode a `Program data: <base64>` log line into a pump.fun event.
+pub fn parse_pump_event(program_data: &str) -> Result<PumpEvent> {
+    let data = program_data
+        .strip_prefix(PROGRAM_DATA_PREFIX)
+        .ok_or_else(|| anyhow!("Invalid program data format"))?;
+
+    let decoded = base64.decode(data)?;
+    decode_pump_event(&decoded)
+}
+
+/// Decode every pump.fun event of a logs notification, in log order.
+///
+/// Lines that are not pump events (other programs in the same transaction may
+/// emit their own `Program data`) are skipped.
+pub fn parse_logs_response(slot: u64, logs: &RpcLogsResponse) -> Vec<TxEvent> {
+    logs.logs
+        .iter()
+        .enumerate()
+        .filter(|(_, log)| log.starts_with(PROGRAM_DATA_PREFIX))
+        .filter_map(|(log_index, log)| match parse_pump_event(log) {
+            Ok(event) => Some(TxEvent::new(
+                EventContext {
+                    signature: logs.signature.clone(),
+                    slot,
+                    log_index,
+                },
+                event,
+            )),
+            Err(e) => {
+                debug!(
+                    "Skip program data, signature: {}, log_index: {}, err: {}",
+                    logs.signature, log_index, e
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+// a buy of 7R4zU5pgHFxRQaNUhhCAPFXaSN6AWiheD6rRfkFJpump, shared by the tests
+#[cfg(test)]
+pub(crate) const TRADE_PROGRAM_DATA: &str = "Program data: vdt/007mYe5fUJLKQBnZyU5a25rXFCHmUq3eDeg/6m3qXr6Y4LVhXz7JvUoAAAAAWdK2IWMiAAABjF9LiRHyIjjqqF93tZIAeB6MsYzDh6xG1Oi/PnwVBw/0JWRnAAAAAERvQMEHAAAANwv2V/5uAwBEwxzFAAAAADdz4wttcAIA";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borsh_string(s: &str) -> Vec<u8> {
+        let mut out = (s.len() as u32).to_le_bytes().to_vec();
+        out.extend_from_slice(s.as_bytes());
+        out
+    }
+
+    #[test]
+    fn test_parse_trade_event() -> Result<()> {
+        match parse_pump_event(TRADE_PROGRAM_DATA)? {
+            PumpEvent::Trade(event) => {
+                assert_eq!(
+                    event.data.mint,
+                    "7R4zU5pgHFxRQaNUhhCAPFXaSN6AWiheD6rRfkFJpump"
+                );
+                assert_eq!(event.data.sol_amount, 1253951806);
+                assert!(event.data.is_buy);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_create_event() -> Result<()> {
+        let mint = Pubkey::new_unique();
+        let bonding_curve = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+
+        let mut data = CREATE_EVENT_DISCRIMINATOR.to_vec();
+        data.extend(borsh_string("Test Coin"));
+        data.extend(borsh_string("TEST"));
+        data.extend(borsh_string("https://example.com/test.json"));
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(bonding_curve.as_ref());
+        data.extend_from_slice(user.as_ref());
+
+        let program_data = format!("{}{}", PROGRAM_DATA_PREFIX, base64.encode(&data));
+        match parse_pump_event(&program_data)? {
+            PumpEvent::Create(event) => {
+                assert_eq!(event.name, "Test Coin");
+                assert_eq!(event.symbol, "TEST");
+                assert_eq!(event.uri, "https://example.com/test.json");
+                assert_eq!(event.mint, mint.to_string());
+                assert_eq!(event.bonding_curve, bonding_curve.to_string());
+                assert_eq!(event.user, user.to_string());
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_complete_event() -> Result<()> {
+        let user = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let bonding_curve = Pubkey::new_unique();
+
+        let mut data = COMPLETE_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(user.as_ref());
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(bonding_curve.as_ref());
+        data.extend_from_slice(&1734616564i64.to_le_bytes());
+
+        let event = decode_pump_event(&data)?;
+        assert_eq!(event.name(), "CompleteEvent");
+        assert_eq!(event.mint(), Some(mint.to_string().as_str()));
+        match event {
+            PumpEvent::Complete(event) => {
+                assert_eq!(event.user, user.to_string());
+                assert_eq!(event.bonding_curve, bonding_curve.to_string());
+                assert_eq!(event.timestamp, 1734616564);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_set_params_event() -> Result<()> {
+        let fee_recipient = Pubkey::new_unique();
+
+        let mut data = SET_PARAMS_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(fee_recipient.as_ref());
+        for v in [
+            1_073_000_000_000_000u64,
+            30_000_000_000,
+            793_100_000_000_000,
+            1_000_000_000_000_000,
+            100,
+        ] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+
+        match decode_pump_event(&data)? {
+            PumpEvent::SetParams(event) => {
+                assert_eq!(event.fee_recipient, fee_recipient.to_string());
+                assert_eq!(event.initial_virtual_sol_reserves, 30_000_000_000);
+                assert_eq!(event.fee_basis_points, 100);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_logs_response() {
+        let mut complete = COMPLETE_EVENT_DISCRIMINATOR.to_vec();
+        complete.extend_from_slice(&[0u8; 32 * 3 + 8]);
+
+        let logs = RpcLogsResponse {
+            signature: "sig".to_string(),
+            err: None,
+            logs: vec![
+                "Program log: Instruction: Buy".to_string(),
+                TRADE_PROGRAM_DATA.to_string(),
+                "Program data: AQIDBAUGBwg=".to_string(),
+                TRADE_PROGRAM_DATA.to_string(),
+                format!("{}{}", PROGRAM_DATA_PREFIX, base64.encode(&complete)),
+            ],
+        };
+
+        let events = parse_logs_response(42, &logs);
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| e.context.log_index)
+                .collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+        assert!(events
+            .iter()
+            .all(|e| e.context.signature == "sig" && e.context.slot == 42));
+        assert_eq!(events[0].event.name(), "TradeEvent");
+        assert_eq!(events[2].event.name(), "CompleteEvent");
+    }
+
+    #[test]
+    fn test_unknown_discriminator() {
+        let data = [1u8, 2, 3, 4, 5, 6, 7, 8, 0, 0];
+        let err = decode_pump_event(&data).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown pump event discriminator: 0102030405060708"));
+    }
+
+    #[test]
+    fn test_truncated_event() {
+        let data = TRADE_EVENT_DISCRIMINATOR.to_vec();
+        assert!(decode_pump_event(&data).is_err());
+        assert!(decode_pump_event(&[0u8; 4]).is_err());
+    }
+}
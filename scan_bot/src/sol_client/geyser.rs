@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use solana_client::rpc_response::RpcLogsResponse;
+use solana_sdk::bs58;
+use tokio::{
+    sync::{mpsc, watch},
+    time::{interval, sleep, timeout, Instant, MissedTickBehavior},
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    client::Grpc,
+    codec::ProstCodec,
+    codegen::http::uri::PathAndQuery,
+    transport::{ClientTlsConfig, Endpoint},
+    Request,
+};
+use tracing::{error, info, warn};
+
+use super::{
+    event::TxEvent,
+    source::{send_events, EventSource},
+    subscription::{
+        next_reconnect_delay, ConnectionState, ConnectionStatus, CONNECT_TIMEOUT,
+        DEAD_SOCKET_TIMEOUT, MIN_RECONNECT_DELAY, PING_INTERVAL,
+    },
+};
+
+const SUBSCRIBE_PATH: &str = "/geyser.Geyser/Subscribe";
+const REQUEST_CHANNEL_CAPACITY: usize = 8;
+
+// the part of yellowstone geyser.proto this client needs, tags as upstream.
+// fields left out are skipped when decoding.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum CommitmentLevel {
+    Processed = 0,
+    Confirmed = 1,
+    Finalized = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeRequest {
+    #[prost(map = "string, message", tag = "3")]
+    pub transactions: HashMap<String, SubscribeRequestFilterTransactions>,
+    #[prost(enumeration = "CommitmentLevel", optional, tag = "6")]
+    pub commitment: Option<i32>,
+    #[prost(message, optional, tag = "9")]
+    pub ping: Option<SubscribeRequestPing>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeRequestFilterTransactions {
+    #[prost(bool, optional, tag = "1")]
+    pub vote: Option<bool>,
+    #[prost(bool, optional, tag = "2")]
+    pub failed: Option<bool>,
+    #[prost(string, repeated, tag = "3")]
+    pub account_include: Vec<String>,
+    #[prost(string, repeated, tag = "4")]
+    pub account_exclude: Vec<String>,
+    #[prost(string, repeated, tag = "6")]
+    pub account_required: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeRequestPing {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdate {
+    #[prost(string, repeated, tag = "1")]
+    pub filters: Vec<String>,
+    #[prost(oneof = "UpdateOneof", tags = "4, 6, 9")]
+    pub update_oneof: Option<UpdateOneof>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum UpdateOneof {
+    #[prost(message, tag = "4")]
+    Transaction(SubscribeUpdateTransaction),
+    #[prost(message, tag = "6")]
+    Ping(SubscribeUpdatePing),
+    #[prost(message, tag = "9")]
+    Pong(SubscribeUpdatePong),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdateTransaction {
+    #[prost(message, optional, tag = "1")]
+    pub transaction: Option<SubscribeUpdateTransactionInfo>,
+    #[prost(uint64, tag = "2")]
+    pub slot: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdateTransactionInfo {
+    #[prost(bytes = "vec", tag = "1")]
+    pub signature: Vec<u8>,
+    #[prost(bool, tag = "2")]
+    pub is_vote: bool,
+    #[prost(message, optional, tag = "4")]
+    pub meta: Option<TransactionStatusMeta>,
+}
+
+// solana.storage.ConfirmedBlock.TransactionStatusMeta
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransactionStatusMeta {
+    #[prost(message, optional, tag = "1")]
+    pub err: Option<TransactionError>,
+    #[prost(string, repeated, tag = "6")]
+    pub log_messages: Vec<String>,
+    #[prost(bool, tag = "11")]
+    pub log_messages_none: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransactionError {
+    #[prost(bytes = "vec", tag = "1")]
+    pub err: Vec<u8>, // bincode of the TransactionError
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdatePing {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdatePong {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+}
+
+fn ping_request() -> SubscribeRequest {
+    SubscribeRequest {
+        ping: Some(SubscribeRequestPing { id: 1 }),
+        ..Default::default()
+    }
+}
+
+// logs of a successful transaction, shaped like a logsSubscribe notification
+fn logs_response(update: &SubscribeUpdateTransaction) -> Option<RpcLogsResponse> {
+    let info = update.transaction.as_ref()?;
+    let meta = info.meta.as_ref()?;
+    if info.is_vote || meta.err.is_some() || meta.log_messages_none {
+        return None;
+    }
+    Some(RpcLogsResponse {
+        signature: bs58::encode(&info.signature).into_string(),
+        err: None,
+        logs: meta.log_messages.clone(),
+    })
+}
+
+/// Transactions mentioning an address from a yellowstone geyser gRPC
+/// `Subscribe` stream. Reconnects with a backoff like the websocket
+/// subscriptions and publishes its status the same way.
+pub struct GrpcSource {
+    url: String,
+    x_token: Option<String>, // auth token most providers require
+    address: String,
+    status: watch::Sender<ConnectionStatus>,
+}
+
+impl GrpcSource {
+    pub fn new(url: &str, x_token: Option<String>, address: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            x_token,
+            address: address.to_string(),
+            status: watch::Sender::new(ConnectionStatus::default()),
+        }
+    }
+
+    pub fn subscribe_request(&self) -> SubscribeRequest {
+        SubscribeRequest {
+            transactions: HashMap::from([(
+                self.address.clone(),
+                SubscribeRequestFilterTransactions {
+                    vote: Some(false),
+                    failed: Some(false),
+                    account_include: vec![self.address.clone()],
+                    ..Default::default()
+                },
+            )]),
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            ..Default::default()
+        }
+    }
+
+    // one stream, returns whether any transaction was received
+    async fn connect(
+        &self,
+        sender: &mpsc::Sender<TxEvent>,
+        disconnected_at: &mut Option<Instant>,
+    ) -> Result<bool> {
+        let mut endpoint =
+            Endpoint::from_shared(self.url.clone())?.connect_timeout(CONNECT_TIMEOUT);
+        if self.url.starts_with("https") {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new().with_native_roots())?;
+        }
+        let mut client = Grpc::new(endpoint.connect().await?);
+        client.ready().await?;
+
+        // the request stream stays open for pings
+        let (requests, request_receiver) = mpsc::channel(REQUEST_CHANNEL_CAPACITY);
+        requests.send(self.subscribe_request()).await?;
+        let mut request = Request::new(ReceiverStream::new(request_receiver));
+        if let Some(x_token) = &self.x_token {
+            request.metadata_mut().insert("x-token", x_token.parse()?);
+        }
+        let mut updates = client
+            .streaming::<_, _, SubscribeUpdate, _>(
+                request,
+                PathAndQuery::from_static(SUBSCRIBE_PATH),
+                ProstCodec::default(),
+            )
+            .await?
+            .into_inner();
+
+        info!("grpc {} subscribed to {}", self.url, self.address);
+        let disconnected_at = disconnected_at.take();
+        self.status
+            .send_modify(|s| s.on_subscribed(disconnected_at, Instant::now()));
+        if disconnected_at.is_some() {
+            warn!(
+                "grpc {} resubscribed after gap: {:?}",
+                self.url,
+                self.status.borrow().last_gap
+            );
+        }
+
+        let mut ping = interval(PING_INTERVAL);
+        ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_seen = Instant::now();
+        let mut received = false;
+        loop {
+            tokio::select! {
+                _ = sender.closed() => return Ok(received),
+                _ = ping.tick() => {
+                    if last_seen.elapsed() > DEAD_SOCKET_TIMEOUT {
+                        return Err(anyhow!("nothing received for {:?}, stream is dead", last_seen.elapsed()));
+                    }
+                    requests.send(ping_request()).await?;
+                }
+                update = timeout(DEAD_SOCKET_TIMEOUT, updates.message()) => {
+                    let Some(update) = update.map_err(|_| anyhow!("nothing received for {:?}, stream is dead", DEAD_SOCKET_TIMEOUT))?? else {
+                        info!("grpc {} stream ended", self.url);
+                        return Ok(received);
+                    };
+                    last_seen = Instant::now();
+                    match update.update_oneof {
+                        Some(UpdateOneof::Transaction(transaction)) => {
+                            received = true;
+                            let slot = transaction.slot;
+                            self.status.send_if_modified(|s| s.on_slot(slot));
+                            if let Some(logs) = logs_response(&transaction) {
+                                if !send_events(sender, slot, &logs).await {
+                                    return Ok(received);
+                                }
+                            }
+                        }
+                        // load balancers drop streams without client traffic
+                        Some(UpdateOneof::Ping(_)) => requests.send(ping_request()).await?,
+                        Some(UpdateOneof::Pong(_)) | None => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for GrpcSource {
+    fn name(&self) -> String {
+        format!("grpc {}", self.url)
+    }
+
+    fn statuses(&self) -> Vec<(String, watch::Receiver<ConnectionStatus>)> {
+        vec![(self.url.clone(), self.status.subscribe())]
+    }
+
+    async fn run(&self, sender: mpsc::Sender<TxEvent>) -> Result<()> {
+        let mut delay = MIN_RECONNECT_DELAY;
+        let mut attempt = 0;
+        let mut disconnected_at = None;
+        loop {
+            self.status
+                .send_modify(|s| s.state = ConnectionState::Connecting);
+            match self.connect(&sender, &mut disconnected_at).await {
+                Ok(true) => {
+                    delay = MIN_RECONNECT_DELAY;
+                    attempt = 0;
+                }
+                Ok(false) => {}
+                Err(e) => error!("grpc {} error: {:?}", self.url, e),
+            }
+            if sender.is_closed() {
+                info!("grpc {} closed", self.url);
+                self.status
+                    .send_modify(|s| s.state = ConnectionState::Closed);
+                return Ok(());
+            }
+
+            disconnected_at.get_or_insert_with(Instant::now);
+            attempt += 1;
+            self.status.send_modify(|s| {
+                s.state = ConnectionState::Reconnecting {
+                    attempt,
+                    retry_in: delay,
+                }
+            });
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = sender.closed() => {}
+            }
+            delay = next_reconnect_delay(delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sol_client::{event::TRADE_PROGRAM_DATA, PUMP_PROGRAM};
+    use futures_util::{stream, Stream, StreamExt};
+    use std::{
+        convert::Infallible,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    };
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{
+        body::BoxBody,
+        codegen::{http, BoxFuture, Service},
+        server::{NamedService, StreamingService},
+        transport::Server,
+        Status, Streaming,
+    };
+
+    // geyser server that sends `updates` to every subscriber, then idles
+    #[derive(Clone)]
+    struct MockGeyser {
+        updates: Vec<SubscribeUpdate>,
+        subscribed: mpsc::Sender<(Option<String>, SubscribeRequest)>, // x-token and request
+    }
+
+    impl NamedService for MockGeyser {
+        const NAME: &'static str = "geyser.Geyser";
+    }
+
+    impl Service<http::Request<BoxBody>> for MockGeyser {
+        type Response = http::Response<BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+            let subscribe = MockSubscribe(self.clone());
+            Box::pin(async move {
+                assert_eq!(request.uri().path(), SUBSCRIBE_PATH);
+                let mut grpc = tonic::server::Grpc::new(ProstCodec::default());
+                Ok(grpc.streaming(subscribe, request).await)
+            })
+        }
+    }
+
+    struct MockSubscribe(MockGeyser);
+
+    type UpdateStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+    impl StreamingService<SubscribeRequest> for MockSubscribe {
+        type Response = SubscribeUpdate;
+        type ResponseStream = UpdateStream;
+        type Future = BoxFuture<tonic::Response<UpdateStream>, Status>;
+
+        fn call(&mut self, request: tonic::Request<Streaming<SubscribeRequest>>) -> Self::Future {
+            let mock = self.0.clone();
+            Box::pin(async move {
+                let x_token = request
+                    .metadata()
+                    .get("x-token")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                let mut requests = request.into_inner();
+                let subscribe = requests
+                    .message()
+                    .await?
+                    .ok_or_else(|| Status::invalid_argument("no subscribe request"))?;
+                mock.subscribed.send((x_token, subscribe)).await.ok();
+                // drain pings
+                tokio::spawn(async move { while let Ok(Some(_)) = requests.message().await {} });
+
+                let updates =
+                    stream::iter(mock.updates.into_iter().map(Ok)).chain(stream::pending());
+                Ok(tonic::Response::new(Box::pin(updates) as UpdateStream))
+            })
+        }
+    }
+
+    fn transaction(signature: u8, failed: bool) -> SubscribeUpdate {
+        SubscribeUpdate {
+            filters: vec![PUMP_PROGRAM.to_string()],
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                transaction: Some(SubscribeUpdateTransactionInfo {
+                    signature: vec![signature; 64],
+                    is_vote: false,
+                    meta: Some(TransactionStatusMeta {
+                        err: failed.then(|| TransactionError { err: vec![1] }),
+                        log_messages: vec![
+                            "Program log: Instruction: Buy".to_string(),
+                            TRADE_PROGRAM_DATA.to_string(),
+                        ],
+                        log_messages_none: false,
+                    }),
+                }),
+                slot: 100 + signature as u64,
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_source() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let (subscribed, mut subscribe_requests) = mpsc::channel(1);
+        let mock = MockGeyser {
+            updates: vec![
+                SubscribeUpdate {
+                    filters: vec![],
+                    update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+                },
+                transaction(1, true),
+                transaction(2, false),
+            ],
+            subscribed,
+        };
+        tokio::spawn(
+            Server::builder()
+                .add_service(mock)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let source = GrpcSource::new(&url, Some("token".to_string()), PUMP_PROGRAM);
+        let mut status = source.statuses().remove(0).1;
+        let (sender, mut receiver) = mpsc::channel(8);
+        let handle = tokio::spawn(async move { source.run(sender).await });
+
+        let event = timeout(Duration::from_secs(5), receiver.recv())
+            .await?
+            .ok_or_else(|| anyhow!("grpc source stopped"))?;
+        // the failed transaction is skipped
+        assert_eq!(
+            event.context.signature,
+            bs58::encode([2u8; 64]).into_string()
+        );
+        assert_eq!(event.context.slot, 102);
+        assert_eq!(event.event.name(), "TradeEvent");
+        assert_eq!(
+            status.borrow_and_update().state,
+            ConnectionState::Subscribed
+        );
+
+        let (x_token, request) = subscribe_requests.recv().await.unwrap();
+        assert_eq!(x_token.as_deref(), Some("token"));
+        assert_eq!(request.commitment, Some(CommitmentLevel::Confirmed as i32));
+        let filter = &request.transactions[PUMP_PROGRAM];
+        assert_eq!(filter.account_include, vec![PUMP_PROGRAM.to_string()]);
+        assert_eq!((filter.vote, filter.failed), (Some(false), Some(false)));
+
+        drop(receiver);
+        timeout(Duration::from_secs(5), handle).await???;
+        assert_eq!(status.borrow().state, ConnectionState::Closed);
+        Ok(())
+    }
+}
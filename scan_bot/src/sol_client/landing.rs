@@ -0,0 +1,132 @@
+use serde_json::Value;
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use tokio::task::JoinHandle;
+
+// pump.fun program errors, anchor custom codes start at 6000
+const PUMP_ERRORS: [&str; 8] = [
+    "NotAuthorized",
+    "AlreadyInitialized",
+    "TooMuchSolRequired",
+    "TooLittleSolReceived",
+    "MintDoesNotMatchBondingCurve",
+    "BondingCurveComplete",
+    "BondingCurveNotComplete",
+    "NotInitialized",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LandingStatus {
+    Landed { signature: String, slot: u64 },
+    Failed { signature: String, error: String },
+    Expired, // never landed before the blockhash / bundle expired
+}
+
+impl LandingStatus {
+    pub fn is_landed(&self) -> bool {
+        matches!(self, LandingStatus::Landed { .. })
+    }
+}
+
+/// Resolves once a sent transaction or bundle has landed, failed or expired.
+pub struct LandingHandle(JoinHandle<LandingStatus>);
+
+impl LandingHandle {
+    pub fn spawn<F>(tracker: F) -> Self
+    where
+        F: std::future::Future<Output = LandingStatus> + Send + 'static,
+    {
+        Self(tokio::spawn(tracker))
+    }
+
+    pub fn ready(status: LandingStatus) -> Self {
+        Self::spawn(async move { status })
+    }
+
+    pub async fn wait(self) -> LandingStatus {
+        self.0.await.unwrap_or_else(|e| LandingStatus::Failed {
+            signature: String::new(),
+            error: format!("landing tracker panicked: {}", e),
+        })
+    }
+}
+
+pub struct SendResult {
+    pub signatures: Vec<String>,
+    pub bundle_id: Option<String>,
+    pub landing: LandingHandle,
+}
+
+/// Readable form of a transaction error, with pump.fun custom codes named.
+pub fn decode_transaction_error(err: &TransactionError) -> String {
+    match err {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+            let name = code
+                .checked_sub(6000)
+                .and_then(|i| PUMP_ERRORS.get(i as usize));
+            match name {
+                Some(name) => format!("instruction {}: {} ({})", index, name, code),
+                None => format!("instruction {}: custom program error {}", index, code),
+            }
+        }
+        TransactionError::InstructionError(index, e) => format!("instruction {}: {}", index, e),
+        e => e.to_string(),
+    }
+}
+
+/// Decode the `err` of a jito bundle status, None when the transaction
+/// succeeded (`{"Ok": null}`).
+pub fn decode_bundle_error(err: &Value) -> Option<String> {
+    if err.is_null() || err.get("Ok").is_some() {
+        return None;
+    }
+    let err = err.get("Err").unwrap_or(err);
+    match serde_json::from_value::<TransactionError>(err.clone()) {
+        Ok(e) => Some(decode_transaction_error(&e)),
+        Err(_) => Some(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_transaction_error() {
+        let err = TransactionError::InstructionError(2, InstructionError::Custom(6002));
+        assert_eq!(
+            decode_transaction_error(&err),
+            "instruction 2: TooMuchSolRequired (6002)"
+        );
+
+        let err = TransactionError::InstructionError(0, InstructionError::Custom(1));
+        assert_eq!(
+            decode_transaction_error(&err),
+            "instruction 0: custom program error 1"
+        );
+
+        let err = TransactionError::BlockhashNotFound;
+        assert_eq!(decode_transaction_error(&err), err.to_string());
+    }
+
+    #[test]
+    fn test_decode_bundle_error() {
+        assert_eq!(decode_bundle_error(&json!({"Ok": null})), None);
+        assert_eq!(decode_bundle_error(&Value::Null), None);
+        assert_eq!(
+            decode_bundle_error(&json!({"Err": {"InstructionError": [3, {"Custom": 6003}]}})),
+            Some("instruction 3: TooLittleSolReceived (6003)".to_string())
+        );
+        assert_eq!(
+            decode_bundle_error(&json!({"Err": "unknown"})),
+            Some("\"unknown\"".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_landing_handle() {
+        let status = LandingHandle::ready(LandingStatus::Expired).wait().await;
+        assert_eq!(status, LandingStatus::Expired);
+        assert!(!status.is_landed());
+    }
+}
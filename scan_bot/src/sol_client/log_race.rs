@@ -0,0 +1,215 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use solana_client::rpc_response::{Response, RpcLogsResponse};
+use tokio::{
+    sync::{mpsc, watch, RwLock},
+    time::{interval, Instant},
+};
+use tracing::info;
+
+use super::subscription::{ConnectionStatus, SubscriptionManager};
+
+// a signature is not expected from a slower provider after this long
+const DEDUP_WINDOW: Duration = Duration::from_secs(60);
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+const RACE_CHANNEL_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderStats {
+    pub url: String,
+    pub first: u64,           // notifications this provider delivered first
+    pub late: u64,            // notifications another provider delivered first
+    pub lead_total: Duration, // summed lead over the providers that came later
+    pub lead_samples: u64,
+}
+
+impl ProviderStats {
+    pub fn mean_lead(&self) -> Duration {
+        if self.lead_samples == 0 {
+            Duration::ZERO
+        } else {
+            self.lead_total / self.lead_samples as u32
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FirstArrival {
+    at: Instant,
+    providers: Vec<usize>, // every provider that delivered it so far
+}
+
+// first arrival of each signature within the dedup window
+#[derive(Debug, Default)]
+struct SignatureDedup {
+    seen: HashMap<String, FirstArrival>,
+    order: VecDeque<(Instant, String)>,
+}
+
+impl SignatureDedup {
+    // whether `signature` is new, the lead of the winner goes into `stats`
+    fn observe(
+        &mut self,
+        provider: usize,
+        signature: &str,
+        now: Instant,
+        stats: &mut [ProviderStats],
+    ) -> bool {
+        while let Some((at, _)) = self.order.front() {
+            if now.duration_since(*at) <= DEDUP_WINDOW {
+                break;
+            }
+            if let Some((_, signature)) = self.order.pop_front() {
+                self.seen.remove(&signature);
+            }
+        }
+
+        match self.seen.get_mut(signature) {
+            None => {
+                self.seen.insert(
+                    signature.to_string(),
+                    FirstArrival {
+                        at: now,
+                        providers: vec![provider],
+                    },
+                );
+                self.order.push_back((now, signature.to_string()));
+                stats[provider].first += 1;
+                true
+            }
+            // the same provider again, e.g. replayed after a reconnect
+            Some(arrival) if arrival.providers.contains(&provider) => false,
+            Some(arrival) => {
+                let winner = arrival.providers[0];
+                arrival.providers.push(provider);
+                stats[winner].lead_total += now.duration_since(arrival.at);
+                stats[winner].lead_samples += 1;
+                stats[provider].late += 1;
+                false
+            }
+        }
+    }
+}
+
+/// The same log subscription on several providers at once. The first
+/// arrival of each signature is forwarded, later ones are counted towards
+/// the lead of the provider that won.
+pub struct LogRace {
+    subscriptions: Vec<(String, Arc<SubscriptionManager<RpcLogsResponse>>)>,
+    stats: Arc<RwLock<Vec<ProviderStats>>>,
+}
+
+impl LogRace {
+    pub fn new(websocket_urls: &[String], address: &str) -> Self {
+        Self {
+            subscriptions: websocket_urls
+                .iter()
+                .map(|url| {
+                    (
+                        url.clone(),
+                        Arc::new(SubscriptionManager::logs(url, address)),
+                    )
+                })
+                .collect(),
+            stats: Arc::new(RwLock::new(
+                websocket_urls
+                    .iter()
+                    .map(|url| ProviderStats {
+                        url: url.clone(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Connection status of each provider.
+    pub fn statuses(&self) -> Vec<(String, watch::Receiver<ConnectionStatus>)> {
+        self.subscriptions
+            .iter()
+            .map(|(url, subscription)| (url.clone(), subscription.status()))
+            .collect()
+    }
+
+    pub async fn stats(&self) -> Vec<ProviderStats> {
+        self.stats.read().await.clone()
+    }
+
+    /// Forward logs of successful transactions, each signature once, until
+    /// the receiver is dropped.
+    pub async fn run(&self, sender: mpsc::Sender<Response<RpcLogsResponse>>) {
+        let (race_sender, mut race_receiver) = mpsc::channel(RACE_CHANNEL_CAPACITY);
+        for (i, (_, subscription)) in self.subscriptions.iter().enumerate() {
+            let subscription = subscription.clone();
+            let race_sender = race_sender.clone();
+            tokio::spawn(async move {
+                subscription
+                    .run(race_sender, move |log: Response<RpcLogsResponse>| {
+                        log.value.err.is_none().then_some((i, log))
+                    })
+                    .await
+            });
+        }
+        drop(race_sender);
+
+        let mut dedup = SignatureDedup::default();
+        let mut stats_log = interval(STATS_LOG_INTERVAL);
+        loop {
+            tokio::select! {
+                received = race_receiver.recv() => {
+                    let Some((provider, log)) = received else {
+                        return;
+                    };
+                    let first = {
+                        let mut stats = self.stats.write().await;
+                        dedup.observe(provider, &log.value.signature, Instant::now(), &mut stats)
+                    };
+                    // dropping race_receiver stops the subscriptions too
+                    if first && sender.send(log).await.is_err() {
+                        return;
+                    }
+                }
+                _ = stats_log.tick() => {
+                    for s in self.stats.read().await.iter() {
+                        info!(
+                            "log race {}: first {}, late {}, mean lead {:?}",
+                            s.url, s.first, s.late, s.mean_lead()
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_dedup() {
+        let now = Instant::now();
+        let mut stats = vec![ProviderStats::default(), ProviderStats::default()];
+        let mut dedup = SignatureDedup::default();
+
+        assert!(dedup.observe(0, "sig1", now, &mut stats));
+        assert!(!dedup.observe(0, "sig1", now, &mut stats));
+        assert!(!dedup.observe(1, "sig1", now + Duration::from_millis(30), &mut stats));
+        assert!(dedup.observe(1, "sig2", now, &mut stats));
+        assert!(!dedup.observe(0, "sig2", now + Duration::from_millis(10), &mut stats));
+
+        assert_eq!((stats[0].first, stats[0].late), (1, 1));
+        assert_eq!((stats[1].first, stats[1].late), (1, 1));
+        assert_eq!(stats[0].mean_lead(), Duration::from_millis(30));
+        assert_eq!(stats[1].mean_lead(), Duration::from_millis(10));
+
+        // forgotten after the window
+        let later = now + DEDUP_WINDOW * 2;
+        assert!(dedup.observe(1, "sig1", later, &mut stats));
+        assert_eq!(dedup.seen.len(), 1);
+    }
+}
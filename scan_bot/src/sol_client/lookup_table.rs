@@ -0,0 +1,159 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use solana_sdk::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    compute_budget,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_program, sysvar,
+    transaction::{Transaction, VersionedTransaction},
+};
+use tokio::sync::{OnceCell, RwLock};
+
+use super::{
+    pump_amm::{PUMP_AMM_EVENT_AUTHORITY, PUMP_AMM_GLOBAL_CONFIG, PUMP_AMM_PROGRAM, WSOL_MINT},
+    ASSOCIATED_TOKEN_PROGRAM, PUMP_ACCOUNT, PUMP_FEE_RECIPIENT, PUMP_GLOBAL, PUMP_PROGRAM,
+};
+
+// an extend instruction must fit in a legacy transaction
+pub const MAX_EXTEND_ADDRESSES: usize = 20;
+
+type LookupTableCache = Arc<RwLock<HashMap<Pubkey, AddressLookupTableAccount>>>;
+
+static LOOKUP_TABLE_CACHE: OnceCell<LookupTableCache> = OnceCell::const_new();
+
+pub async fn get_lookup_table_cache() -> &'static LookupTableCache {
+    LOOKUP_TABLE_CACHE
+        .get_or_init(|| async { Arc::new(RwLock::new(HashMap::new())) })
+        .await
+}
+
+/// Accounts every pump swap touches, worth keeping in a lookup table.
+pub fn pump_static_accounts() -> Vec<Pubkey> {
+    vec![
+        Pubkey::from_str_const(PUMP_GLOBAL),
+        Pubkey::from_str_const(PUMP_FEE_RECIPIENT),
+        Pubkey::from_str_const(PUMP_ACCOUNT),
+        Pubkey::from_str_const(PUMP_PROGRAM),
+        Pubkey::from_str_const(PUMP_AMM_GLOBAL_CONFIG),
+        Pubkey::from_str_const(PUMP_AMM_EVENT_AUTHORITY),
+        Pubkey::from_str_const(PUMP_AMM_PROGRAM),
+        Pubkey::from_str_const(WSOL_MINT),
+        Pubkey::from_str_const(ASSOCIATED_TOKEN_PROGRAM),
+        spl_token::ID,
+        system_program::id(),
+        sysvar::rent::id(),
+        compute_budget::id(),
+    ]
+}
+
+/// Decode a lookup table account into what `v0::Message` compiles against.
+pub fn decode_lookup_table(key: Pubkey, data: &[u8]) -> Result<AddressLookupTableAccount> {
+    let table = AddressLookupTable::deserialize(data)
+        .map_err(|e| anyhow!("Failed to decode lookup table {}, err: {}", key, e))?;
+    Ok(AddressLookupTableAccount {
+        key,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Sign `instructions` into a transaction. With lookup tables it is a v0
+/// transaction, otherwise a legacy one.
+pub fn build_versioned_transaction(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    lookup_tables: Option<&[AddressLookupTableAccount]>,
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    build_signed_transaction(
+        &payer.pubkey(),
+        &[payer],
+        instructions,
+        lookup_tables,
+        recent_blockhash,
+    )
+}
+
+/// Like `build_versioned_transaction`, signed by every keypair in `signers`,
+/// which must be exactly the signers `instructions` and `payer` need.
+pub fn build_signed_transaction(
+    payer: &Pubkey,
+    signers: &[&Keypair],
+    instructions: &[Instruction],
+    lookup_tables: Option<&[AddressLookupTableAccount]>,
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    match lookup_tables {
+        Some(lookup_tables) => {
+            let message =
+                v0::Message::try_compile(payer, instructions, lookup_tables, recent_blockhash)?;
+            Ok(VersionedTransaction::try_new(
+                VersionedMessage::V0(message),
+                signers,
+            )?)
+        }
+        None => {
+            let mut transaction = Transaction::new_with_payer(instructions, Some(payer));
+            transaction.try_sign(signers, recent_blockhash)?;
+            Ok(transaction.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[test]
+    fn test_build_versioned_transaction() -> Result<()> {
+        let payer = Keypair::new();
+        let accounts = pump_static_accounts();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::from_str_const(PUMP_PROGRAM),
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new_readonly(accounts[0], false),
+                AccountMeta::new(accounts[1], false),
+                AccountMeta::new_readonly(accounts[2], false),
+                AccountMeta::new(payer.pubkey(), true),
+            ],
+        );
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: accounts,
+        };
+
+        let instructions = vec![ix];
+        let lookup_tables = vec![table];
+        let legacy = build_versioned_transaction(&payer, &instructions, None, Hash::default())?;
+        let v0 = build_versioned_transaction(
+            &payer,
+            &instructions,
+            Some(&lookup_tables),
+            Hash::default(),
+        )?;
+
+        assert!(matches!(legacy.message, VersionedMessage::Legacy(_)));
+        match &v0.message {
+            VersionedMessage::V0(message) => {
+                // payer and program stay static, the rest is looked up
+                assert_eq!(message.account_keys.len(), 2);
+                assert_eq!(message.address_table_lookups.len(), 1);
+                assert_eq!(
+                    message.address_table_lookups[0].account_key,
+                    lookup_tables[0].key
+                );
+            }
+            _ => panic!("expect v0 message"),
+        }
+        assert!(v0.verify_with_results().iter().all(|ok| *ok));
+        assert!(bincode::serialize(&v0)?.len() < bincode::serialize(&legacy)?.len());
+        Ok(())
+    }
+}
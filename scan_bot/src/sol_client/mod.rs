@@ -1,13 +1,29 @@
+pub mod backfill;
+pub mod bundle;
 pub mod client;
+pub mod curve_watch;
+pub mod event;
+pub mod geyser;
+pub mod landing;
+pub mod log_race;
+pub mod lookup_table;
+pub mod priority_fee;
+pub mod pump_amm;
+pub mod quote;
+pub mod replay;
+pub mod rpc_pool;
+pub mod source;
+pub mod subscription;
 
 use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
-use base64::{engine::general_purpose::STANDARD as base64, Engine};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 
+use event::{parse_pump_event, PumpEvent};
+
 pub const TEN_THOUSAND: u64 = 10000;
 pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
 pub const RENT_PROGRAM: &str = "SysvarRent111111111111111111111111111111111";
@@ -20,7 +36,7 @@ pub const PUMP_MINT: &str = "TSLvdd1pWpHVjahSpsvCXUbgwsL3JAcvokwaKt1eokM";
 pub const PUMP_BUY_METHOD: u64 = 16927863322537952870;
 pub const PUMP_SELL_METHOD: u64 = 12502976635542562355;
 
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct BondingCurveAccount {
     pub discriminator: u64,
     pub virtual_token_reserves: u64,
@@ -60,57 +76,20 @@ impl TradeEventData {
 }
 
 pub fn parse_log_subscribe_data(program_data: &str) -> Result<TradeEvent> {
-    // Remove "Program data: " prefix
-    let data = program_data
-        .strip_prefix("Program data: ")
-        .ok_or_else(|| anyhow!("Invalid program data format"))?;
-
-    // Decode base64
-    let decoded = base64.decode(data)?;
-    /* TradeEvent 和decoded的字节的关系如下：
-       event_flag: 8 byte
-       mint: 32 byte
-       solAmount: 8 byte
-       tokenAmount: 8 byte
-       isBuy: 1 byte
-       user: 32 byte
-       timestamp: 8 byte
-       virtualSolReserves: 8 byte
-       virtualTokenReserves: 8 byte
-       realSolReserves: 8 byte
-       realTokenReserves: 8 byte
-    */
-
-    // 8 + 32 + 8 + 8 + 1 + 32 + 8 + 8 + 8 + 8 + 8 = 129
-    if decoded.len() % 129 != 0 {
-        return Err(anyhow!(format!(
-            "Invalid program data length: {}",
-            decoded.len()
-        )));
+    match parse_pump_event(program_data)? {
+        PumpEvent::Trade(trade_event) => Ok(trade_event),
+        other => Err(anyhow!("Expected TradeEvent, got {}", other.name())),
     }
-    // decode
-    let mint_arr: [u8; 32] = decoded[8..40].try_into().unwrap();
-    let user_arr: [u8; 32] = decoded[57..89].try_into().unwrap();
-    let trade_event: TradeEvent = TradeEvent {
-        name: "TradeEvent".to_string(),
-        data: TradeEventData {
-            mint: Pubkey::new_from_array(mint_arr).to_string(),
-            sol_amount: u64::from_le_bytes(decoded[40..48].try_into().unwrap()),
-            token_amount: u64::from_le_bytes(decoded[48..56].try_into().unwrap()),
-            is_buy: decoded[56] != 0,
-            user: Pubkey::new_from_array(user_arr).to_string(),
-            timestamp: i64::from_le_bytes(decoded[89..97].try_into().unwrap()),
-            virtual_sol_reserves: u64::from_le_bytes(decoded[97..105].try_into().unwrap()),
-            virtual_token_reserves: u64::from_le_bytes(decoded[105..113].try_into().unwrap()),
-            real_sol_reserves: u64::from_le_bytes(decoded[113..121].try_into().unwrap()),
-            real_token_reserves: u64::from_le_bytes(decoded[121..129].try_into().unwrap()),
-        },
-    };
-
-    Ok(trade_event)
 }
 
 impl BondingCurveAccount {
+    // live accounts are larger than this struct (creator, padding), the
+    // trailing bytes are ignored
+    pub fn decode(mut data: &[u8]) -> Result<Self> {
+        Self::deserialize(&mut data)
+            .map_err(|e| anyhow!("Failed to decode bonding curve account data, err: {}", e))
+    }
+
     pub fn get_price(&self) -> f64 {
         let virtual_sol_reserves = self.virtual_sol_reserves as f64 / 1_000_000_000.0;
         let virtual_token_reserves = self.virtual_token_reserves as f64 / 1_000_000.0;
@@ -149,7 +128,7 @@ mod tests {
             }
         }
          */
-        let program_data = "Program data: vdt/007mYe5fUJLKQBnZyU5a25rXFCHmUq3eDeg/6m3qXr6Y4LVhXz7JvUoAAAAAWdK2IWMiAAABjF9LiRHyIjjqqF93tZIAeB6MsYzDh6xG1Oi/PnwVBw/0JWRnAAAAAERvQMEHAAAANwv2V/5uAwBEwxzFAAAAADdz4wttcAIA";
+        let program_data = event::TRADE_PROGRAM_DATA;
 
         let event = parse_log_subscribe_data(program_data)?;
 
@@ -161,7 +140,7 @@ mod tests {
         );
         assert_eq!(event.data.sol_amount, 1253951806);
         assert_eq!(event.data.token_amount, 37809162736217);
-        assert_eq!(event.data.is_buy, true);
+        assert!(event.data.is_buy);
         assert_eq!(
             event.data.user,
             "ASxMiMb1AJGTU4AduPNB2CGqT1TiDqWkLvy7oCUnzw5x"
@@ -0,0 +1,171 @@
+use serde::Deserialize;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, pubkey::Pubkey,
+};
+
+// getRecentPrioritizationFees accepts at most 128 accounts
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum SendMode {
+    #[default]
+    Jito, // jito bundle with a tip transfer
+    JitoTransaction, // jito sendTransaction with a tip transfer
+    Rpc,             // sendTransaction with compute budget priority fee
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SendConfig {
+    #[serde(default)]
+    pub mode: SendMode,
+    #[serde(default = "default_compute_unit_limit")]
+    pub compute_unit_limit: u32, // cu limit of the transaction
+    #[serde(default = "default_priority_fee_percentile")]
+    pub priority_fee_percentile: u32, // 0-100, percentile of recent prioritization fees
+    #[serde(default)]
+    pub min_compute_unit_price: u64, // micro lamports per cu
+    #[serde(default = "default_max_compute_unit_price")]
+    pub max_compute_unit_price: u64, // micro lamports per cu
+    #[serde(default)]
+    pub versioned_transaction: bool, // send v0 transactions
+    #[serde(default)]
+    pub lookup_tables: Vec<String>, // lookup tables for v0 transactions
+    #[serde(default)]
+    pub jito_bundle_only: bool, // JitoTransaction mode: bundleOnly, revert protected
+}
+
+fn default_compute_unit_limit() -> u32 {
+    200_000
+}
+
+fn default_priority_fee_percentile() -> u32 {
+    75
+}
+
+fn default_max_compute_unit_price() -> u64 {
+    1_000_000 // 0.0002 sol with 200k cu
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        Self {
+            mode: SendMode::default(),
+            compute_unit_limit: default_compute_unit_limit(),
+            priority_fee_percentile: default_priority_fee_percentile(),
+            min_compute_unit_price: 0,
+            max_compute_unit_price: default_max_compute_unit_price(),
+            versioned_transaction: false,
+            lookup_tables: vec![],
+            jito_bundle_only: false,
+        }
+    }
+}
+
+impl SendConfig {
+    /// Compute unit price from recent prioritization fees, clamped to the
+    /// configured min and max.
+    pub fn compute_unit_price(&self, recent_fees: &[u64]) -> u64 {
+        fee_percentile(recent_fees, self.priority_fee_percentile)
+            .unwrap_or(self.min_compute_unit_price)
+            .clamp(
+                self.min_compute_unit_price,
+                self.max_compute_unit_price.max(self.min_compute_unit_price),
+            )
+    }
+
+    pub fn compute_budget_instructions(&self, compute_unit_price: u64) -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(self.compute_unit_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+        ]
+    }
+}
+
+/// Nearest-rank percentile of `fees`, None when empty.
+pub fn fee_percentile(fees: &[u64], percentile: u32) -> Option<u64> {
+    if fees.is_empty() {
+        return None;
+    }
+    let mut fees = fees.to_vec();
+    fees.sort_unstable();
+    let rank = (percentile.min(100) as usize * fees.len()).div_ceil(100);
+    Some(fees[rank.saturating_sub(1)])
+}
+
+/// Writable accounts of `instructions`, the ones whose fee market matters.
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| ix.accounts.iter()) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts.truncate(MAX_FEE_ACCOUNTS);
+    accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[test]
+    fn test_fee_percentile() {
+        let fees = [50, 10, 40, 20, 30];
+        assert_eq!(fee_percentile(&fees, 0), Some(10));
+        assert_eq!(fee_percentile(&fees, 50), Some(30));
+        assert_eq!(fee_percentile(&fees, 75), Some(40));
+        assert_eq!(fee_percentile(&fees, 100), Some(50));
+        assert_eq!(fee_percentile(&fees, 200), Some(50));
+        assert_eq!(fee_percentile(&[], 50), None);
+    }
+
+    #[test]
+    fn test_compute_unit_price() {
+        let config = SendConfig {
+            mode: SendMode::Rpc,
+            compute_unit_limit: 100_000,
+            priority_fee_percentile: 50,
+            min_compute_unit_price: 1_000,
+            max_compute_unit_price: 100_000,
+            ..Default::default()
+        };
+        assert_eq!(config.compute_unit_price(&[]), 1_000);
+        assert_eq!(config.compute_unit_price(&[0, 0, 10]), 1_000);
+        assert_eq!(config.compute_unit_price(&[5_000, 20_000, 30_000]), 20_000);
+        assert_eq!(config.compute_unit_price(&[500_000]), 100_000);
+
+        let instructions = config.compute_budget_instructions(20_000);
+        assert_eq!(instructions.len(), 2);
+    }
+
+    #[test]
+    fn test_send_config() -> Result<(), toml::de::Error> {
+        let config: SendConfig = toml::from_str(
+            r#"
+            mode = "JitoTransaction"
+            jito_bundle_only = true
+            "#,
+        )?;
+        assert_eq!(config.mode, SendMode::JitoTransaction);
+        assert!(config.jito_bundle_only);
+        assert_eq!(config.compute_unit_limit, 200_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_writable_accounts() {
+        let writable = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![
+                AccountMeta::new(writable, false),
+                AccountMeta::new_readonly(readonly, false),
+                AccountMeta::new(writable, false),
+            ],
+        );
+        assert_eq!(writable_accounts(&[ix]), vec![writable]);
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::{anyhow, Result};
+use borsh::BorshDeserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use super::{quote::CurveReserves, PUMP_PROGRAM};
+
+// pump.fun bonding curves migrate to the pump swap amm once complete
+pub const PUMP_AMM_PROGRAM: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+pub const PUMP_AMM_GLOBAL_CONFIG: &str = "ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw";
+pub const PUMP_AMM_EVENT_AUTHORITY: &str = "GS4CU59F31iL7aR2Q8zVS8DRrcRnXX1yjQ66TqNVQnaR";
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const POOL_ACCOUNT_DISCRIMINATOR: u64 = 13577703138238765809;
+pub const GLOBAL_CONFIG_ACCOUNT_DISCRIMINATOR: u64 = 15686315269655627925;
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct PoolState {
+    pub discriminator: u64,
+    pub pool_bump: u8,
+    pub index: u16,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub lp_supply: u64,
+    pub coin_creator: Pubkey, // default for pools created before creator fees
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct GlobalConfig {
+    pub discriminator: u64,
+    pub admin: Pubkey,
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    pub disable_flags: u8,
+    pub protocol_fee_recipients: [Pubkey; 8],
+    pub coin_creator_fee_basis_points: u64,
+}
+
+// layout of the pool before `coin_creator` was appended
+#[derive(BorshDeserialize)]
+struct LegacyPoolState {
+    discriminator: u64,
+    pool_bump: u8,
+    index: u16,
+    creator: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    lp_mint: Pubkey,
+    pool_base_token_account: Pubkey,
+    pool_quote_token_account: Pubkey,
+    lp_supply: u64,
+}
+
+impl PoolState {
+    pub fn decode(mut data: &[u8]) -> Result<Self> {
+        let legacy = LegacyPoolState::deserialize(&mut data)
+            .map_err(|e| anyhow!("Failed to decode pool account data, err: {}", e))?;
+        if legacy.discriminator != POOL_ACCOUNT_DISCRIMINATOR {
+            return Err(anyhow!(
+                "Invalid pool account discriminator: {}",
+                legacy.discriminator
+            ));
+        }
+        let coin_creator = Pubkey::deserialize(&mut data).unwrap_or_default();
+
+        Ok(Self {
+            discriminator: legacy.discriminator,
+            pool_bump: legacy.pool_bump,
+            index: legacy.index,
+            creator: legacy.creator,
+            base_mint: legacy.base_mint,
+            quote_mint: legacy.quote_mint,
+            lp_mint: legacy.lp_mint,
+            pool_base_token_account: legacy.pool_base_token_account,
+            pool_quote_token_account: legacy.pool_quote_token_account,
+            lp_supply: legacy.lp_supply,
+            coin_creator,
+        })
+    }
+}
+
+impl GlobalConfig {
+    pub fn decode(mut data: &[u8]) -> Result<Self> {
+        let config = Self::deserialize(&mut data)
+            .map_err(|e| anyhow!("Failed to decode amm global config data, err: {}", e))?;
+        if config.discriminator != GLOBAL_CONFIG_ACCOUNT_DISCRIMINATOR {
+            return Err(anyhow!(
+                "Invalid amm global config discriminator: {}",
+                config.discriminator
+            ));
+        }
+        Ok(config)
+    }
+
+    // lp + protocol + coin creator fee
+    pub fn total_fee_basis_points(&self) -> u64 {
+        self.lp_fee_basis_points
+            + self.protocol_fee_basis_points
+            + self.coin_creator_fee_basis_points
+    }
+
+    pub fn protocol_fee_recipient(&self) -> Result<Pubkey> {
+        self.protocol_fee_recipients
+            .iter()
+            .find(|r| **r != Pubkey::default())
+            .copied()
+            .ok_or_else(|| anyhow!("No amm protocol fee recipient configured"))
+    }
+}
+
+/// Everything needed to price and build a swap against a migrated pool.
+#[derive(Debug, Clone)]
+pub struct AmmPool {
+    pub address: Pubkey,
+    pub state: PoolState,
+    pub global_config: GlobalConfig,
+    pub base_reserves: u64,  // token in the pool
+    pub quote_reserves: u64, // wsol in the pool
+}
+
+impl AmmPool {
+    /// Pool reserves in bonding curve terms, so the curve quoter prices amm
+    /// swaps too: constant product, buy fee on top, sell fee deducted.
+    pub fn reserves(&self) -> CurveReserves {
+        CurveReserves {
+            virtual_sol_reserves: self.quote_reserves,
+            virtual_token_reserves: self.base_reserves,
+            real_sol_reserves: self.quote_reserves,
+            real_token_reserves: self.base_reserves,
+        }
+    }
+}
+
+/// Authority that migrates the bonding curve of `mint` and creates its pool.
+pub fn get_pool_authority(mint: &Pubkey) -> Pubkey {
+    let (pool_authority, _bump) = Pubkey::find_program_address(
+        &[b"pool-authority", mint.as_ref()],
+        &Pubkey::from_str_const(PUMP_PROGRAM),
+    );
+    pool_authority
+}
+
+/// Canonical pump swap pool (index 0, quoted in wsol) of a migrated `mint`.
+pub fn get_pool_pda(mint: &Pubkey) -> Pubkey {
+    let pool_authority = get_pool_authority(mint);
+    let wsol = Pubkey::from_str_const(WSOL_MINT);
+    let (pool, _bump) = Pubkey::find_program_address(
+        &[
+            b"pool",
+            &0u16.to_le_bytes(),
+            pool_authority.as_ref(),
+            mint.as_ref(),
+            wsol.as_ref(),
+        ],
+        &Pubkey::from_str_const(PUMP_AMM_PROGRAM),
+    );
+    pool
+}
+
+pub fn get_coin_creator_vault_authority(coin_creator: &Pubkey) -> Pubkey {
+    let (vault_authority, _bump) = Pubkey::find_program_address(
+        &[b"creator_vault", coin_creator.as_ref()],
+        &Pubkey::from_str_const(PUMP_AMM_PROGRAM),
+    );
+    vault_authority
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::to_vec;
+
+    #[test]
+    fn test_static_pdas() {
+        let program = Pubkey::from_str_const(PUMP_AMM_PROGRAM);
+        let (global_config, _) = Pubkey::find_program_address(&[b"global_config"], &program);
+        let (event_authority, _) = Pubkey::find_program_address(&[b"__event_authority"], &program);
+
+        assert_eq!(global_config.to_string(), PUMP_AMM_GLOBAL_CONFIG);
+        assert_eq!(event_authority.to_string(), PUMP_AMM_EVENT_AUTHORITY);
+    }
+
+    #[test]
+    fn test_decode_pool_state() -> Result<()> {
+        let base_mint = Pubkey::new_unique();
+        let coin_creator = Pubkey::new_unique();
+
+        let mut data = to_vec(&POOL_ACCOUNT_DISCRIMINATOR)?;
+        data.push(255);
+        data.extend(to_vec(&0u16)?);
+        for key in [
+            Pubkey::new_unique(),
+            base_mint,
+            Pubkey::from_str_const(WSOL_MINT),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ] {
+            data.extend_from_slice(key.as_ref());
+        }
+        data.extend(to_vec(&1_000u64)?);
+
+        // legacy pool without coin creator
+        let legacy = PoolState::decode(&data)?;
+        assert_eq!(legacy.base_mint, base_mint);
+        assert_eq!(legacy.lp_supply, 1_000);
+        assert_eq!(legacy.coin_creator, Pubkey::default());
+
+        data.extend_from_slice(coin_creator.as_ref());
+        let pool = PoolState::decode(&data)?;
+        assert_eq!(pool.coin_creator, coin_creator);
+
+        data[0] = 0;
+        assert!(PoolState::decode(&data).is_err());
+        Ok(())
+    }
+}
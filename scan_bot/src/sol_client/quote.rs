@@ -0,0 +1,304 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{BondingCurveAccount, TradeEventData, TEN_THOUSAND};
+
+// pump.fun global fee, 1%
+pub const PUMP_FEE_BASIS_POINTS: u64 = 100;
+
+/// Reserves of a bonding curve, enough to price trades against it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurveReserves {
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    pub amount_in: u64,    // buy: lamports in (fee included), sell: tokens in
+    pub amount_out: u64,   // buy: tokens out, sell: lamports out (fee deducted)
+    pub fee: u64,          // protocol fee in lamports
+    pub price_before: f64, // sol per token before the trade
+    pub price_after: f64,  // sol per token after the trade
+    pub price_impact: f64, // (price_after - price_before) / price_before
+    pub reserves_after: CurveReserves,
+}
+
+impl From<&BondingCurveAccount> for CurveReserves {
+    fn from(account: &BondingCurveAccount) -> Self {
+        Self {
+            virtual_sol_reserves: account.virtual_sol_reserves,
+            virtual_token_reserves: account.virtual_token_reserves,
+            real_sol_reserves: account.real_sol_reserves,
+            real_token_reserves: account.real_token_reserves,
+        }
+    }
+}
+
+impl From<&TradeEventData> for CurveReserves {
+    fn from(data: &TradeEventData) -> Self {
+        Self {
+            virtual_sol_reserves: data.virtual_sol_reserves,
+            virtual_token_reserves: data.virtual_token_reserves,
+            real_sol_reserves: data.real_sol_reserves,
+            real_token_reserves: data.real_token_reserves,
+        }
+    }
+}
+
+fn checked_u64(v: u128) -> Result<u64> {
+    u64::try_from(v).map_err(|_| anyhow!("quote: amount overflows u64: {}", v))
+}
+
+// a * b / c, rounded down
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    if c == 0 {
+        return Err(anyhow!("quote: division by zero"));
+    }
+    checked_u64((a as u128) * (b as u128) / (c as u128))
+}
+
+// a * b / c, rounded up
+fn mul_div_ceil(a: u64, b: u64, c: u64) -> Result<u64> {
+    if c == 0 {
+        return Err(anyhow!("quote: division by zero"));
+    }
+    checked_u64(((a as u128) * (b as u128)).div_ceil(c as u128))
+}
+
+/// Protocol fee charged on `lamports`.
+pub fn get_fee(lamports: u64, fee_basis_points: u64) -> Result<u64> {
+    mul_div_ceil(lamports, fee_basis_points, TEN_THOUSAND)
+}
+
+impl CurveReserves {
+    pub fn get_price(&self) -> f64 {
+        let virtual_sol_reserves = self.virtual_sol_reserves as f64 / 1_000_000_000.0;
+        let virtual_token_reserves = self.virtual_token_reserves as f64 / 1_000_000.0;
+        virtual_sol_reserves / virtual_token_reserves
+    }
+
+    fn quote(&self, amount_in: u64, amount_out: u64, fee: u64, after: CurveReserves) -> Quote {
+        let price_before = self.get_price();
+        let price_after = after.get_price();
+        Quote {
+            amount_in,
+            amount_out,
+            fee,
+            price_before,
+            price_after,
+            price_impact: (price_after - price_before) / price_before,
+            reserves_after: after,
+        }
+    }
+
+    /// Tokens received for spending `sol_in` lamports, fee included.
+    pub fn quote_buy(&self, sol_in: u64, fee_basis_points: u64) -> Result<Quote> {
+        if sol_in == 0 {
+            return Err(anyhow!("quote: buy amount must be greater than 0"));
+        }
+        // sol_in = sol_cost + fee, fee = sol_cost * bps / 10000
+        let sol_cost = mul_div(sol_in, TEN_THOUSAND, TEN_THOUSAND + fee_basis_points)?;
+        let fee = sol_in - sol_cost;
+
+        let new_virtual_sol_reserves = self
+            .virtual_sol_reserves
+            .checked_add(sol_cost)
+            .ok_or_else(|| anyhow!("quote: virtual sol reserves overflow"))?;
+        let token_out = mul_div(
+            sol_cost,
+            self.virtual_token_reserves,
+            new_virtual_sol_reserves,
+        )?;
+        if token_out == 0 {
+            return Err(anyhow!("quote: buy amount too small, 0 token out"));
+        }
+        if token_out > self.real_token_reserves {
+            return Err(anyhow!(
+                "quote: token out {} exceeds real token reserves {}",
+                token_out,
+                self.real_token_reserves
+            ));
+        }
+
+        let after = CurveReserves {
+            virtual_sol_reserves: new_virtual_sol_reserves,
+            virtual_token_reserves: self.virtual_token_reserves - token_out,
+            real_sol_reserves: self
+                .real_sol_reserves
+                .checked_add(sol_cost)
+                .ok_or_else(|| anyhow!("quote: real sol reserves overflow"))?,
+            real_token_reserves: self.real_token_reserves - token_out,
+        };
+        Ok(self.quote(sol_in, token_out, fee, after))
+    }
+
+    /// Lamports needed to buy exactly `token_out` tokens, fee included.
+    pub fn quote_buy_exact_tokens(&self, token_out: u64, fee_basis_points: u64) -> Result<Quote> {
+        if token_out == 0 {
+            return Err(anyhow!("quote: token amount must be greater than 0"));
+        }
+        if token_out > self.real_token_reserves {
+            return Err(anyhow!(
+                "quote: token out {} exceeds real token reserves {}",
+                token_out,
+                self.real_token_reserves
+            ));
+        }
+        let remaining = self
+            .virtual_token_reserves
+            .checked_sub(token_out)
+            .filter(|v| *v > 0)
+            .ok_or_else(|| anyhow!("quote: token out exceeds virtual token reserves"))?;
+
+        let sol_cost = mul_div_ceil(token_out, self.virtual_sol_reserves, remaining)?;
+        let fee = get_fee(sol_cost, fee_basis_points)?;
+        let sol_in = sol_cost
+            .checked_add(fee)
+            .ok_or_else(|| anyhow!("quote: sol in overflow"))?;
+
+        let after = CurveReserves {
+            virtual_sol_reserves: self
+                .virtual_sol_reserves
+                .checked_add(sol_cost)
+                .ok_or_else(|| anyhow!("quote: virtual sol reserves overflow"))?,
+            virtual_token_reserves: remaining,
+            real_sol_reserves: self
+                .real_sol_reserves
+                .checked_add(sol_cost)
+                .ok_or_else(|| anyhow!("quote: real sol reserves overflow"))?,
+            real_token_reserves: self.real_token_reserves - token_out,
+        };
+        Ok(self.quote(sol_in, token_out, fee, after))
+    }
+
+    /// Lamports received for selling `token_in` tokens, fee deducted.
+    pub fn quote_sell(&self, token_in: u64, fee_basis_points: u64) -> Result<Quote> {
+        if token_in == 0 {
+            return Err(anyhow!("quote: sell amount must be greater than 0"));
+        }
+        let new_virtual_token_reserves = self
+            .virtual_token_reserves
+            .checked_add(token_in)
+            .ok_or_else(|| anyhow!("quote: virtual token reserves overflow"))?;
+        let sol_out = mul_div(
+            token_in,
+            self.virtual_sol_reserves,
+            new_virtual_token_reserves,
+        )?;
+        if sol_out > self.real_sol_reserves {
+            return Err(anyhow!(
+                "quote: sol out {} exceeds real sol reserves {}",
+                sol_out,
+                self.real_sol_reserves
+            ));
+        }
+        let fee = get_fee(sol_out, fee_basis_points)?;
+
+        let after = CurveReserves {
+            virtual_sol_reserves: self.virtual_sol_reserves - sol_out,
+            virtual_token_reserves: new_virtual_token_reserves,
+            real_sol_reserves: self.real_sol_reserves - sol_out,
+            real_token_reserves: self
+                .real_token_reserves
+                .checked_add(token_in)
+                .ok_or_else(|| anyhow!("quote: real token reserves overflow"))?,
+        };
+        Ok(self.quote(token_in, sol_out.saturating_sub(fee), fee, after))
+    }
+}
+
+impl BondingCurveAccount {
+    pub fn reserves(&self) -> CurveReserves {
+        CurveReserves::from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // initial pump.fun curve
+    fn initial_reserves() -> CurveReserves {
+        CurveReserves {
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            real_sol_reserves: 0,
+            real_token_reserves: 793_100_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_quote_buy() -> Result<()> {
+        let reserves = initial_reserves();
+        let quote = reserves.quote_buy(1_010_000_000, PUMP_FEE_BASIS_POINTS)?;
+
+        assert_eq!(quote.fee, 10_000_000);
+        // 1 sol into 30 sol virtual: 1e9 * 1.073e15 / 31e9
+        assert_eq!(quote.amount_out, 34_612_903_225_806);
+        assert_eq!(quote.reserves_after.virtual_sol_reserves, 31_000_000_000);
+        assert_eq!(quote.reserves_after.real_sol_reserves, 1_000_000_000);
+        assert!(quote.price_after > quote.price_before);
+        assert!((quote.price_impact - 0.0677).abs() < 0.001);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_buy_exact_tokens_round_trip() -> Result<()> {
+        let reserves = initial_reserves();
+        let buy = reserves.quote_buy(1_010_000_000, PUMP_FEE_BASIS_POINTS)?;
+        let exact = reserves.quote_buy_exact_tokens(buy.amount_out, PUMP_FEE_BASIS_POINTS)?;
+
+        // paying the exact cost never costs more than the budget
+        assert!(exact.amount_in <= buy.amount_in);
+        assert!(buy.amount_in - exact.amount_in < 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_sell() -> Result<()> {
+        let reserves = initial_reserves();
+        let buy = reserves.quote_buy(1_010_000_000, PUMP_FEE_BASIS_POINTS)?;
+        let sell = buy
+            .reserves_after
+            .quote_sell(buy.amount_out, PUMP_FEE_BASIS_POINTS)?;
+
+        // selling back everything returns the sol put in, minus fee and rounding
+        assert_eq!(sell.fee, 10_000_000);
+        assert!(sell.amount_out <= 990_000_000);
+        assert!(990_000_000 - sell.amount_out < 10);
+        assert!(sell.price_impact < 0.0);
+        assert_eq!(sell.reserves_after.real_token_reserves, 793_100_000_000_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_exceeds_real_reserves() {
+        let reserves = initial_reserves();
+        // more than the ~85 sol the curve can take
+        assert!(reserves
+            .quote_buy(200_000_000_000, PUMP_FEE_BASIS_POINTS)
+            .is_err());
+        assert!(reserves
+            .quote_buy_exact_tokens(793_100_000_000_001, PUMP_FEE_BASIS_POINTS)
+            .is_err());
+        // nothing to pay out on a fresh curve
+        assert!(reserves
+            .quote_sell(1_000_000, PUMP_FEE_BASIS_POINTS)
+            .is_err());
+        assert!(reserves.quote_buy(0, PUMP_FEE_BASIS_POINTS).is_err());
+    }
+
+    #[test]
+    fn test_quote_overflow() {
+        let reserves = CurveReserves {
+            virtual_sol_reserves: u64::MAX,
+            virtual_token_reserves: u64::MAX,
+            real_sol_reserves: u64::MAX,
+            real_token_reserves: u64::MAX,
+        };
+        assert!(reserves.quote_buy(u64::MAX, 0).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use solana_client::rpc_response::{Response, RpcLogsResponse};
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, BufReader},
+    sync::mpsc,
+    time::sleep,
+};
+use tracing::{info, warn};
+
+use super::{
+    event::TxEvent,
+    source::{send_events, EventSource},
+};
+
+const SLOT_DURATION: Duration = Duration::from_millis(400);
+
+// one recorded line, either the notification result or the whole
+// `logsNotification` message as captured off the socket
+fn parse_line(line: &str) -> Result<Response<RpcLogsResponse>> {
+    let mut v: Value = serde_json::from_str(line)?;
+    if let Some(result) = v.get_mut("params").and_then(|p| p.get_mut("result")) {
+        v = result.take();
+    }
+    Ok(serde_json::from_value(v)?)
+}
+
+// how long to wait before replaying `slot`, 0 speed replays without pause
+fn replay_delay(last_slot: Option<u64>, slot: u64, speed: f64) -> Duration {
+    match last_slot {
+        Some(last) if speed > 0.0 && slot > last => {
+            SLOT_DURATION.mul_f64((slot - last) as f64 / speed)
+        }
+        _ => Duration::ZERO,
+    }
+}
+
+/// Logs notifications recorded to a file, one json per line, replayed in
+/// file order. With a positive `speed` the slot spacing is kept, 2.0 plays
+/// twice as fast as recorded.
+pub struct ReplaySource {
+    path: String,
+    speed: f64,
+}
+
+impl ReplaySource {
+    pub fn new(path: &str, speed: f64) -> Self {
+        Self {
+            path: path.to_string(),
+            speed,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for ReplaySource {
+    fn name(&self) -> String {
+        format!("replay of {}", self.path)
+    }
+
+    async fn run(&self, sender: mpsc::Sender<TxEvent>) -> Result<()> {
+        let file = File::open(&self.path)
+            .await
+            .map_err(|e| anyhow!("replay: open {} failed: {}", self.path, e))?;
+        let mut lines = BufReader::new(file).lines();
+        let mut line_number = 0;
+        let mut last_slot = None;
+        let mut replayed = 0;
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let logs = match parse_line(&line) {
+                Ok(logs) => logs,
+                Err(e) => {
+                    warn!("replay: skip {}:{}: {}", self.path, line_number, e);
+                    continue;
+                }
+            };
+            if logs.value.err.is_some() {
+                continue;
+            }
+
+            let slot = logs.context.slot;
+            sleep(replay_delay(last_slot, slot, self.speed)).await;
+            last_slot = Some(slot);
+            if !send_events(&sender, slot, &logs.value).await {
+                return Ok(());
+            }
+            replayed += 1;
+        }
+        info!("replay: {} done, {} transactions", self.path, replayed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sol_client::event::TRADE_PROGRAM_DATA;
+    use serde_json::json;
+    use std::io::Write;
+
+    #[test]
+    fn test_replay_delay() {
+        assert_eq!(replay_delay(None, 10, 1.0), Duration::ZERO);
+        assert_eq!(replay_delay(Some(10), 12, 0.0), Duration::ZERO);
+        assert_eq!(replay_delay(Some(10), 12, 1.0), Duration::from_millis(800));
+        assert_eq!(replay_delay(Some(10), 12, 2.0), Duration::from_millis(400));
+        // out of order lines are not waited for
+        assert_eq!(replay_delay(Some(12), 10, 1.0), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_replay_source() -> Result<()> {
+        let logs = |signature: &str, err: Value| {
+            json!({
+                "signature": signature,
+                "err": err,
+                "logs": ["Program log: Instruction: Buy", TRADE_PROGRAM_DATA],
+            })
+        };
+        let path = std::env::temp_dir().join(format!("replay-{}.jsonl", std::process::id()));
+        let mut file = std::fs::File::create(&path)?;
+        writeln!(
+            file,
+            "{}",
+            json!({"context": {"slot": 1}, "value": logs("sig1", Value::Null)})
+        )?;
+        writeln!(file)?;
+        writeln!(file, "not json")?;
+        writeln!(
+            file,
+            "{}",
+            json!({"context": {"slot": 2}, "value": logs("failed", json!({"InstructionError": [0, {"Custom": 1}]}))})
+        )?;
+        writeln!(
+            file,
+            "{}",
+            json!({
+                "jsonrpc": "2.0",
+                "method": "logsNotification",
+                "params": {
+                    "result": {"context": {"slot": 3}, "value": logs("sig2", Value::Null)},
+                    "subscription": 1
+                }
+            })
+        )?;
+        drop(file);
+
+        let source = ReplaySource::new(path.to_str().unwrap(), 0.0);
+        let (sender, mut receiver) = mpsc::channel(8);
+        source.run(sender).await?;
+        std::fs::remove_file(&path)?;
+
+        let mut events = vec![];
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
+        }
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.context.signature.as_str(), e.context.slot))
+                .collect::<Vec<_>>(),
+            vec![("sig1", 1), ("sig2", 3)]
+        );
+        assert!(events.iter().all(|e| e.event.name() == "TradeEvent"));
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_custom_error::{
+        JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+        JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+    },
+    rpc_request::RpcError,
+};
+use tokio::time::timeout;
+use tracing::warn;
+
+pub const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+// generic json-rpc internal error, what most providers return when overloaded
+const JSON_RPC_INTERNAL_ERROR: i64 = -32603;
+
+/// Rpc providers tried in turn. Calls go to the provider that last answered
+/// and fail over to the next one on a transport error, an unhealthy node or
+/// a timeout. Errors about the request itself are returned right away.
+pub struct RpcPool {
+    endpoints: Vec<(String, Arc<RpcClient>)>,
+    timeout: Duration, // per call and provider
+    preferred: AtomicUsize,
+}
+
+impl RpcPool {
+    pub fn new(urls: &[String], timeout: Duration) -> Self {
+        Self {
+            endpoints: urls
+                .iter()
+                .map(|url| (url.clone(), Arc::new(RpcClient::new(url.clone()))))
+                .collect(),
+            timeout,
+            preferred: AtomicUsize::new(0),
+        }
+    }
+
+    /// Client of the provider that last answered.
+    pub fn client(&self) -> Arc<RpcClient> {
+        self.endpoints[self.preferred.load(Ordering::Relaxed) % self.endpoints.len()]
+            .1
+            .clone()
+    }
+
+    /// Run `f` against each provider in turn until one answers.
+    pub async fn call<T, F, Fut>(&self, name: &str, f: F) -> Result<T>
+    where
+        F: Fn(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        self.call_with_timeout(name, self.timeout, f).await
+    }
+
+    /// Like `call`, for calls that take longer than the pool timeout.
+    pub async fn call_with_timeout<T, F, Fut>(
+        &self,
+        name: &str,
+        call_timeout: Duration,
+        f: F,
+    ) -> Result<T>
+    where
+        F: Fn(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        if self.endpoints.is_empty() {
+            return Err(anyhow!("rpc: no provider configured"));
+        }
+        let preferred = self.preferred.load(Ordering::Relaxed);
+        let mut last_error = None;
+        for i in failover_order(preferred, self.endpoints.len()) {
+            let (url, client) = &self.endpoints[i];
+            let error = match timeout(call_timeout, f(client.clone())).await {
+                Ok(Ok(value)) => {
+                    if i != preferred {
+                        warn!("rpc: {} failed over to {}", name, url);
+                        self.preferred.store(i, Ordering::Relaxed);
+                    }
+                    return Ok(value);
+                }
+                Ok(Err(e)) if !should_fail_over(&e) => return Err(e.into()),
+                Ok(Err(e)) => anyhow!("rpc: {} on {} failed: {}", name, url, e),
+                Err(_) => anyhow!(
+                    "rpc: {} on {} timed out after {:?}",
+                    name,
+                    url,
+                    call_timeout
+                ),
+            };
+            warn!("{}", error);
+            last_error = Some(error);
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("rpc: {} failed", name)))
+    }
+}
+
+// the preferred provider first, then the others in configured order
+fn failover_order(preferred: usize, len: usize) -> impl Iterator<Item = usize> {
+    (0..len).map(move |i| (preferred + i) % len)
+}
+
+// whether another provider might answer where this one failed
+fn should_fail_over(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) | ClientErrorKind::Middleware(_) => {
+            true
+        }
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_))
+        | ClientErrorKind::RpcError(RpcError::ParseError(_)) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => matches!(
+            *code,
+            JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                | JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE
+                | JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+                | JSON_RPC_INTERNAL_ERROR
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcResponseErrorData;
+
+    fn response_error(code: i64) -> ClientError {
+        ClientError::from(RpcError::RpcResponseError {
+            code,
+            message: String::new(),
+            data: RpcResponseErrorData::Empty,
+        })
+    }
+
+    #[test]
+    fn test_failover_order() {
+        assert_eq!(failover_order(0, 3).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(failover_order(2, 3).collect::<Vec<_>>(), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn test_should_fail_over() {
+        assert!(should_fail_over(&ClientError::from(
+            RpcError::RpcRequestError("connection refused".to_string())
+        )));
+        assert!(should_fail_over(&response_error(
+            JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+        )));
+        assert!(should_fail_over(&response_error(JSON_RPC_INTERNAL_ERROR)));
+        // the request is wrong, every provider would say so
+        assert!(!should_fail_over(&response_error(-32602)));
+        assert!(!should_fail_over(&ClientError::from(
+            ClientErrorKind::Custom("invalid".to_string())
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_call_fails_over() -> Result<()> {
+        let pool = RpcPool::new(
+            &["http://a".to_string(), "http://b".to_string()],
+            Duration::from_secs(1),
+        );
+        let value = pool
+            .call("test", |client| async move {
+                if client.url() == "http://a" {
+                    Err(RpcError::RpcRequestError("down".to_string()).into())
+                } else {
+                    Ok(client.url())
+                }
+            })
+            .await?;
+        assert_eq!(value, "http://b");
+        // b answered, it is tried first from now on
+        assert_eq!(pool.client().url(), "http://b");
+
+        let err = pool
+            .call("test", |_| async { Err::<(), _>(response_error(-32602)) })
+            .await;
+        assert!(err.is_err());
+        assert_eq!(pool.client().url(), "http://b");
+        Ok(())
+    }
+}
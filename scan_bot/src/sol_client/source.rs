@@ -0,0 +1,158 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use solana_client::rpc_response::{Response, RpcLogsResponse};
+use tokio::sync::{mpsc, watch};
+
+use super::{
+    event::{parse_logs_response, TxEvent},
+    geyser::GrpcSource,
+    log_race::LogRace,
+    replay::ReplaySource,
+    subscription::ConnectionStatus,
+};
+
+const LOG_CHANNEL_CAPACITY: usize = 1000;
+
+/// A feed of decoded pump events of successful transactions. Strategies
+/// consume any source the same way.
+#[async_trait]
+pub trait EventSource: Send + Sync {
+    fn name(&self) -> String;
+
+    /// Connection status of each upstream, empty when there is none.
+    fn statuses(&self) -> Vec<(String, watch::Receiver<ConnectionStatus>)> {
+        vec![]
+    }
+
+    /// Send events until the receiver is dropped or the source runs out.
+    async fn run(&self, sender: mpsc::Sender<TxEvent>) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum EventSourceKind {
+    #[default]
+    WebSocket,
+    Grpc {
+        url: String,
+        #[serde(default)]
+        x_token: Option<String>,
+    },
+    Replay {
+        path: String,
+        #[serde(default)]
+        speed: f64,
+    },
+}
+
+impl EventSourceKind {
+    pub fn build(&self, websocket_urls: &[String], address: &str) -> Box<dyn EventSource> {
+        match self {
+            EventSourceKind::WebSocket => Box::new(WebSocketSource::new(websocket_urls, address)),
+            EventSourceKind::Grpc { url, x_token } => {
+                Box::new(GrpcSource::new(url, x_token.clone(), address))
+            }
+            EventSourceKind::Replay { path, speed } => Box::new(ReplaySource::new(path, *speed)),
+        }
+    }
+}
+
+// decode and send the events of one transaction, false once the receiver is gone
+pub(crate) async fn send_events(
+    sender: &mpsc::Sender<TxEvent>,
+    slot: u64,
+    logs: &RpcLogsResponse,
+) -> bool {
+    for event in parse_logs_response(slot, logs) {
+        if sender.send(event).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// `logsSubscribe` raced over every websocket provider.
+pub struct WebSocketSource {
+    race: LogRace,
+    providers: usize,
+}
+
+impl WebSocketSource {
+    pub fn new(websocket_urls: &[String], address: &str) -> Self {
+        Self {
+            race: LogRace::new(websocket_urls, address),
+            providers: websocket_urls.len(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for WebSocketSource {
+    fn name(&self) -> String {
+        format!("websocket logsSubscribe on {} providers", self.providers)
+    }
+
+    fn statuses(&self) -> Vec<(String, watch::Receiver<ConnectionStatus>)> {
+        self.race.statuses()
+    }
+
+    async fn run(&self, sender: mpsc::Sender<TxEvent>) -> Result<()> {
+        let (log_sender, mut log_receiver) =
+            mpsc::channel::<Response<RpcLogsResponse>>(LOG_CHANNEL_CAPACITY);
+        let forward = async move {
+            while let Some(logs) = log_receiver.recv().await {
+                if !send_events(&sender, logs.context.slot, &logs.value).await {
+                    return;
+                }
+            }
+        };
+        // the race stops once forward drops log_receiver
+        tokio::join!(self.race.run(log_sender), forward);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Sources {
+        sources: Vec<EventSourceKind>,
+    }
+
+    #[test]
+    fn test_event_source_kind() -> Result<()> {
+        let sources: Sources = toml::from_str(
+            r#"
+            [[sources]]
+            type = "WebSocket"
+
+            [[sources]]
+            type = "Grpc"
+            url = "http://127.0.0.1:10000"
+
+            [[sources]]
+            type = "Replay"
+            path = "logs.jsonl"
+            speed = 2.0
+            "#,
+        )?;
+        assert_eq!(
+            sources.sources,
+            vec![
+                EventSourceKind::WebSocket,
+                EventSourceKind::Grpc {
+                    url: "http://127.0.0.1:10000".to_string(),
+                    x_token: None,
+                },
+                EventSourceKind::Replay {
+                    path: "logs.jsonl".to_string(),
+                    speed: 2.0,
+                },
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+use std::{marker::PhantomData, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccount;
+use solana_client::rpc_response::{Response, RpcKeyedAccount, RpcLogsResponse};
+use tokio::{
+    sync::{mpsc::Sender, watch},
+    time::{interval, sleep, timeout, Instant, MissedTickBehavior},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, error, info, warn};
+
+pub(crate) const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const PING_INTERVAL: Duration = Duration::from_secs(10);
+// nothing received for this long, not even a pong, means the socket is dead
+pub(crate) const DEAD_SOCKET_TIMEOUT: Duration = Duration::from_secs(30);
+pub(crate) const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    Subscribed,
+    Reconnecting { attempt: u32, retry_in: Duration },
+    Closed, // the receiver was dropped
+}
+
+/// Notifications possibly missed while the subscription was down. Slots
+/// after `last_slot` and before `resumed_slot` may need a backfill.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionGap {
+    pub last_slot: Option<u64>,    // last slot seen before the disconnect
+    pub resumed_slot: Option<u64>, // first slot after resubscribing, once known
+    pub downtime: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionStatus {
+    pub state: ConnectionState,
+    pub last_slot: Option<u64>,
+    pub reconnects: u64,
+    pub last_gap: Option<SubscriptionGap>,
+}
+
+impl Default for ConnectionStatus {
+    fn default() -> Self {
+        Self {
+            state: ConnectionState::Connecting,
+            last_slot: None,
+            reconnects: 0,
+            last_gap: None,
+        }
+    }
+}
+
+impl ConnectionStatus {
+    // `disconnected_at` is None on the first subscribe
+    pub(crate) fn on_subscribed(&mut self, disconnected_at: Option<Instant>, now: Instant) {
+        self.state = ConnectionState::Subscribed;
+        if let Some(disconnected_at) = disconnected_at {
+            self.reconnects += 1;
+            self.last_gap = Some(SubscriptionGap {
+                last_slot: self.last_slot,
+                resumed_slot: None,
+                downtime: now.duration_since(disconnected_at),
+            });
+        }
+    }
+
+    // returns whether the gap was closed, last_slot alone is not worth waking
+    // up consumers for
+    pub(crate) fn on_slot(&mut self, slot: u64) -> bool {
+        self.last_slot = Some(self.last_slot.map_or(slot, |last| last.max(slot)));
+        match self.last_gap.as_mut() {
+            Some(gap) if gap.resumed_slot.is_none() => {
+                gap.resumed_slot = Some(slot);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum WsMessage<T> {
+    Subscribed(u64), // subscription id
+    Notification(Response<T>),
+    Error(Value),
+    Other,
+}
+
+fn parse_message<T: DeserializeOwned>(text: &str) -> Result<WsMessage<T>> {
+    let v: Value = serde_json::from_str(text)?;
+    if let Some(error) = v.get("error") {
+        return Ok(WsMessage::Error(error.clone()));
+    }
+    if let Some(result) = v.get("params").and_then(|p| p.get("result")) {
+        return Ok(WsMessage::Notification(serde_json::from_value(
+            result.clone(),
+        )?));
+    }
+    if let Some(id) = v.get("result").and_then(Value::as_u64) {
+        return Ok(WsMessage::Subscribed(id));
+    }
+    Ok(WsMessage::Other)
+}
+
+pub(crate) fn next_reconnect_delay(delay: Duration) -> Duration {
+    (delay * 2).min(MAX_RECONNECT_DELAY)
+}
+
+/// One websocket subscription that stays up: pings keep the socket alive,
+/// a dead socket is dropped and the subscription is made again after a
+/// backoff. Connection state and gaps are published on `status`.
+pub struct SubscriptionManager<T> {
+    websocket_url: String,
+    subscribe: Value, // subscribe request, sent again on every reconnect
+    status: watch::Sender<ConnectionStatus>,
+    _notification: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> SubscriptionManager<T> {
+    pub fn new(websocket_url: &str, subscribe: Value) -> Self {
+        let (status, _) = watch::channel(ConnectionStatus::default());
+        Self {
+            websocket_url: websocket_url.to_string(),
+            subscribe,
+            status,
+            _notification: PhantomData,
+        }
+    }
+
+    pub fn status(&self) -> watch::Receiver<ConnectionStatus> {
+        self.status.subscribe()
+    }
+
+    /// Forward notifications `map` keeps to `sender`, reconnecting as
+    /// needed. Returns once the receiver is dropped.
+    pub async fn run<U, F>(&self, sender: Sender<U>, map: F)
+    where
+        F: Fn(Response<T>) -> Option<U>,
+    {
+        let mut delay = MIN_RECONNECT_DELAY;
+        let mut attempt = 0;
+        let mut disconnected_at = None;
+        loop {
+            self.status
+                .send_modify(|s| s.state = ConnectionState::Connecting);
+            match self.connect(&sender, &map, &mut disconnected_at).await {
+                // received data, the connection was healthy
+                Ok(true) => {
+                    delay = MIN_RECONNECT_DELAY;
+                    attempt = 0;
+                }
+                Ok(false) => {}
+                Err(e) => error!("subscription {} error: {:?}", self.websocket_url, e),
+            }
+            if sender.is_closed() {
+                info!("subscription {} closed", self.websocket_url);
+                self.status
+                    .send_modify(|s| s.state = ConnectionState::Closed);
+                return;
+            }
+
+            disconnected_at.get_or_insert_with(Instant::now);
+            attempt += 1;
+            warn!(
+                "subscription {} disconnected, reconnecting in {:?} (attempt {})",
+                self.websocket_url, delay, attempt
+            );
+            self.status.send_modify(|s| {
+                s.state = ConnectionState::Reconnecting {
+                    attempt,
+                    retry_in: delay,
+                }
+            });
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = sender.closed() => {}
+            }
+            delay = next_reconnect_delay(delay);
+        }
+    }
+
+    // one connection, returns whether any notification was received
+    async fn connect<U, F>(
+        &self,
+        sender: &Sender<U>,
+        map: &F,
+        disconnected_at: &mut Option<Instant>,
+    ) -> Result<bool>
+    where
+        F: Fn(Response<T>) -> Option<U>,
+    {
+        let (ws_stream, _) = timeout(CONNECT_TIMEOUT, connect_async(&self.websocket_url))
+            .await
+            .context("connect timed out")??;
+        let (mut write, mut read) = ws_stream.split();
+        write
+            .send(Message::text(self.subscribe.to_string()))
+            .await?;
+
+        let mut ping = interval(PING_INTERVAL);
+        ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_seen = Instant::now();
+        let mut received = false;
+
+        loop {
+            tokio::select! {
+                _ = sender.closed() => return Ok(received),
+                _ = ping.tick() => {
+                    if last_seen.elapsed() > DEAD_SOCKET_TIMEOUT {
+                        return Err(anyhow!("nothing received for {:?}, socket is dead", last_seen.elapsed()));
+                    }
+                    write.send(Message::Ping(Default::default())).await?;
+                }
+                msg = read.next() => {
+                    let Some(msg) = msg else {
+                        info!("subscription {} stream ended", self.websocket_url);
+                        return Ok(received);
+                    };
+                    last_seen = Instant::now();
+                    match msg? {
+                        Message::Text(text) => match parse_message::<T>(&text) {
+                            Ok(WsMessage::Subscribed(id)) => {
+                                info!("subscription {} subscribed, id: {}", self.websocket_url, id);
+                                let disconnected_at = disconnected_at.take();
+                                self.status.send_modify(|s| s.on_subscribed(disconnected_at, Instant::now()));
+                                if disconnected_at.is_some() {
+                                    warn!("subscription {} resubscribed after gap: {:?}", self.websocket_url, self.status.borrow().last_gap);
+                                }
+                            }
+                            Ok(WsMessage::Notification(response)) => {
+                                received = true;
+                                let slot = response.context.slot;
+                                self.status.send_if_modified(|s| s.on_slot(slot));
+                                if let Some(item) = map(response) {
+                                    if sender.send(item).await.is_err() {
+                                        return Ok(received);
+                                    }
+                                }
+                            }
+                            Ok(WsMessage::Error(e)) => return Err(anyhow!("subscribe failed: {}", e)),
+                            Ok(WsMessage::Other) => debug!("Receive not result message: {}", text),
+                            Err(e) => debug!("Receive can't parse json message: {}, err: {}", text, e),
+                        },
+                        Message::Close(frame) => {
+                            info!("subscription {} closed by server: {:?}", self.websocket_url, frame);
+                            return Ok(received);
+                        }
+                        // pongs only count as a sign of life
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl SubscriptionManager<RpcLogsResponse> {
+    /// `logsSubscribe` to transactions mentioning `address`.
+    pub fn logs(websocket_url: &str, address: &str) -> Self {
+        Self::new(
+            websocket_url,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "logsSubscribe",
+                "params": [
+                    {
+                        "mentions": [address]
+                    },
+                    {
+                        "commitment": "confirmed"
+                    }
+                ]
+            }),
+        )
+    }
+
+    /// Forward logs of successful transactions.
+    pub async fn run_logs(&self, sender: Sender<Response<RpcLogsResponse>>) {
+        self.run(sender, |log| log.value.err.is_none().then_some(log))
+            .await
+    }
+}
+
+impl SubscriptionManager<RpcKeyedAccount> {
+    /// `programSubscribe` to accounts owned by `address`.
+    pub fn program(websocket_url: &str, address: &str) -> Self {
+        Self::new(
+            websocket_url,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "programSubscribe",
+                "params": [
+                    address,
+                    {
+                        "commitment": "confirmed",
+                        "encoding": "jsonParsed"
+                    }
+                ]
+            }),
+        )
+    }
+
+    pub async fn run_accounts(&self, sender: Sender<RpcKeyedAccount>) {
+        self.run(sender, |account| Some(account.value)).await
+    }
+}
+
+impl SubscriptionManager<UiAccount> {
+    /// `accountSubscribe` to changes of the account at `address`.
+    pub fn account(websocket_url: &str, address: &str) -> Self {
+        Self::new(
+            websocket_url,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "accountSubscribe",
+                "params": [
+                    address,
+                    {
+                        "commitment": "confirmed",
+                        "encoding": "base64"
+                    }
+                ]
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message() -> Result<()> {
+        let subscribed = r#"{"jsonrpc":"2.0","result":42,"id":1}"#;
+        assert!(matches!(
+            parse_message::<RpcLogsResponse>(subscribed)?,
+            WsMessage::Subscribed(42)
+        ));
+
+        let notification = r#"{"jsonrpc":"2.0","method":"logsNotification","params":{
+            "result":{"context":{"slot":100},"value":{"signature":"sig1","err":null,"logs":["Program log: Instruction: Buy"]}},
+            "subscription":42}}"#;
+        match parse_message::<RpcLogsResponse>(notification)? {
+            WsMessage::Notification(log) => {
+                assert_eq!(log.context.slot, 100);
+                assert_eq!(log.value.signature, "sig1");
+            }
+            m => panic!("expect notification, got {:?}", m),
+        }
+
+        let error =
+            r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params"},"id":1}"#;
+        assert!(matches!(
+            parse_message::<RpcLogsResponse>(error)?,
+            WsMessage::Error(_)
+        ));
+        assert!(parse_message::<RpcLogsResponse>("not json").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_connection_status_gap() {
+        let now = Instant::now();
+        let mut status = ConnectionStatus::default();
+        status.on_subscribed(None, now);
+        assert!(!status.on_slot(100));
+        assert!(!status.on_slot(99));
+        assert_eq!(status.last_slot, Some(100));
+        assert_eq!(status.last_gap, None);
+
+        status.on_subscribed(Some(now), now + Duration::from_secs(3));
+        assert_eq!(status.state, ConnectionState::Subscribed);
+        assert_eq!(status.reconnects, 1);
+        assert!(status.on_slot(110));
+        assert!(!status.on_slot(111));
+        assert_eq!(
+            status.last_gap,
+            Some(SubscriptionGap {
+                last_slot: Some(100),
+                resumed_slot: Some(110),
+                downtime: Duration::from_secs(3),
+            })
+        );
+    }
+
+    #[test]
+    fn test_next_reconnect_delay() {
+        assert_eq!(
+            next_reconnect_delay(MIN_RECONNECT_DELAY),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            next_reconnect_delay(Duration::from_secs(20)),
+            MAX_RECONNECT_DELAY
+        );
+    }
+}
@@ -1,15 +1,17 @@
 use anyhow::Result;
 use scan_dealer::{get_global_statistics_manager, init_statistics_manager};
 use serde::Deserialize;
-use solana_client::rpc_response::RpcLogsResponse;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{mpsc, OnceCell, RwLock};
-use tracing::{debug, error};
+use tokio::sync::{mpsc, watch, OnceCell, RwLock};
+use tracing::{debug, info, warn};
 
 use crate::{
     config::get_global_config,
-    sol_client::{client::SolanaMonitor, parse_log_subscribe_data},
+    sol_client::{
+        event::{PumpEvent, TxEvent},
+        subscription::{ConnectionState, ConnectionStatus},
+    },
 };
 
 pub mod scan_dealer;
@@ -45,11 +47,6 @@ pub enum MonitorRuleType {
     ScanDealer, // 扫描庄
 }
 
-const DATA_FLAG: &str = "Program data: vdt";
-const BUY_FLAG: &str = "Program log: Instruction: Buy";
-const _SELL_FLAG: &str = "Program log: Instruction: Sell";
-const _CREATE_FLAG: &str = "Program log: Instruction: Create";
-
 impl MonitorRule {
     pub async fn should_alert(&self) -> Result<()> {
         let c = get_global_config().await;
@@ -61,35 +58,81 @@ impl MonitorRule {
                 statistic.start_monitor().await;
 
                 // 监控pump_program,如果有币在短期内急速上涨,则买入
-                let (sender, mut receiver) = mpsc::channel::<RpcLogsResponse>(1000);
+                let (sender, mut receiver) = mpsc::channel::<TxEvent>(1000);
 
-                let address = self.address.clone();
-                tokio::spawn(async move {
-                    let solana_client = SolanaMonitor::new(&c.solana_wss_url, &c.solana_rpc_url);
-                    solana_client
-                        .start_log_subscribe(&address, sender, None)
-                        .await
-                        .unwrap();
-                });
+                let source = c.event_source.build(&c.wss_urls(), &self.address);
+                info!("{} events from {}", self.address, source.name());
+                for (url, status) in source.statuses() {
+                    tokio::spawn(log_connection_status(self.address.clone(), url, status));
+                }
+                let feed = tokio::spawn(async move { source.run(sender).await });
 
-                while let Some(logs) = receiver.recv().await {
-                    debug!("log: {:?}", logs);
-                    if logs.logs.contains(&BUY_FLAG.to_string()) {
-                        for log in logs.clone().logs {
-                            if log.contains(&DATA_FLAG) {
-                                if let Ok(event) = parse_log_subscribe_data(&log) {
-                                    self.deal_scan_dealer(&event).await?;
-                                } else {
-                                    error!("parse_program_data error: {:?}", logs);
-                                    break;
-                                }
-                                break;
-                            }
-                        }
-                    }
+                while let Some(event) = receiver.recv().await {
+                    debug!("event: {:?}", event);
+                    self.deal_event(event).await?;
                 }
+                // only a replay runs out
+                feed.await??;
+                info!("event source of {} finished", self.address);
                 Ok(())
             }
         }
     }
+
+    async fn deal_event(&self, tx_event: TxEvent) -> Result<()> {
+        let TxEvent { context, event } = tx_event;
+        match event {
+            PumpEvent::Trade(trade_event) => {
+                // 只统计买入
+                if trade_event.data.is_buy {
+                    self.deal_scan_dealer(TxEvent::new(context, trade_event))
+                        .await?;
+                }
+            }
+            PumpEvent::Create(create_event) => {
+                info!(
+                    "New coin created: {} ({}), mint: {}, signature: {}",
+                    create_event.name, create_event.symbol, create_event.mint, context.signature
+                );
+            }
+            PumpEvent::Complete(complete_event) => {
+                info!(
+                    "Bonding curve complete, mint: {}, bonding curve: {}, signature: {}",
+                    complete_event.mint, complete_event.bonding_curve, context.signature
+                );
+            }
+            PumpEvent::SetParams(set_params_event) => {
+                info!("Pump params updated: {:?}", set_params_event);
+            }
+        }
+        Ok(())
+    }
+}
+
+// warn about disconnects and the gaps they leave, until the subscription ends
+async fn log_connection_status(
+    address: String,
+    url: String,
+    mut status: watch::Receiver<ConnectionStatus>,
+) {
+    while status.changed().await.is_ok() {
+        let status = status.borrow_and_update().clone();
+        match status.state {
+            ConnectionState::Subscribed => {
+                if let Some(gap) = status.last_gap {
+                    warn!(
+                        "{} resubscribed on {}, events may be missing: {:?}",
+                        address, url, gap
+                    );
+                }
+            }
+            ConnectionState::Reconnecting { attempt, retry_in } => {
+                warn!(
+                    "{} disconnected from {}, retry {} in {:?}, last slot: {:?}",
+                    address, url, attempt, retry_in, status.last_slot
+                );
+            }
+            _ => {}
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::sol_client::TradeEvent;
+use crate::sol_client::{event::TxEvent, TradeEvent};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
@@ -19,11 +19,13 @@ pub struct ScanDealerConfig {
     pub holding_time_threshold: u64, // 统计持仓时间阈值(s)
 }
 
+type EventMap = HashMap<i64, HashMap<String, Vec<TxEvent<TradeEvent>>>>;
+
 pub struct Statistics {
     // 在同一秒内，可能有多个事件，所以这里用Vec
-    pub statistics_map: Arc<RwLock<HashMap<i64, HashMap<String, Vec<TradeEvent>>>>>, // (时间戳， (币， 交易量))
-    pub holding_time_threshold: Duration, // 持仓时间阈值，超过这个时间就不跟踪了
-    pub alarm_threshold: f64,             // 警报阈值，超过这个阈值就警报,累计多少个sol
+    pub statistics_map: Arc<RwLock<EventMap>>, // (时间戳， (币， 交易量))
+    pub holding_time_threshold: Duration,      // 持仓时间阈值，超过这个时间就不跟踪了
+    pub alarm_threshold: f64,                  // 警报阈值，超过这个阈值就警报,累计多少个sol
 }
 
 static GLOBAL_STATISTICS_MANAGER: OnceCell<Arc<Statistics>> = OnceCell::const_new();
@@ -87,12 +89,12 @@ impl Statistics {
                         // 检查这些时间里的购买sol的数量是否都在15%的误差范围
                         let mut first_sol: f64 = 0.0;
                         let mut will_alarm = true;
-                        for i in 0..3 {
-                            let event = &events[i];
+                        for (i, event) in events.iter().take(3).enumerate() {
                             if i == 0 {
-                                first_sol = event.data.sol_amount as f64 / 1_000_000_000.0;
+                                first_sol = event.event.data.sol_amount as f64 / 1_000_000_000.0;
                             } else {
-                                let sol_amount = event.data.sol_amount as f64 / 1_000_000_000.0;
+                                let sol_amount =
+                                    event.event.data.sol_amount as f64 / 1_000_000_000.0;
                                 if (sol_amount - first_sol).abs() > first_sol * 0.15 {
                                     // 超过15%的误差
                                     will_alarm = false;
@@ -101,11 +103,14 @@ impl Statistics {
                             }
                         }
                         if will_alarm {
+                            let last = &events[events.len() - 1];
                             warn!(
-                                "----> Alarm: {}, sol: {}, price: {}",
+                                "----> Alarm: {}, sol: {}, price: {}, slot: {}, signature: {}",
                                 coin,
                                 first_sol,
-                                events[events.len() - 1].data.get_price()
+                                last.event.data.get_price(),
+                                last.context.slot,
+                                last.context.signature
                             );
                         }
                     }
@@ -121,32 +126,33 @@ impl Statistics {
         });
     }
 
-    async fn add_event(&self, event: &TradeEvent) {
+    async fn add_event(&self, event: &TxEvent<TradeEvent>) {
         // check shield
         debug!("Add event: {:?}", event);
 
-        let sol_amount = event.data.sol_amount as f64 / 1_000_000_000.0;
-        let price = event.data.get_price();
-        if event.data.is_buy {
-            if sol_amount < 0.5 {
-                return;
-            }
+        let data = &event.event.data;
+        let sol_amount = data.sol_amount as f64 / 1_000_000_000.0;
+        let price = data.get_price();
+        if data.is_buy && sol_amount < 0.5 {
+            return;
         }
 
         debug!(
-            "Add event: {}, amount: {}, price: {}",
-            event.data.mint, sol_amount, price
+            "Add event: {}, amount: {}, price: {}, signature: {}",
+            data.mint, sol_amount, price, event.context.signature
         );
 
         let mut event_map = self.statistics_map.write().await;
-        let data = event.data.clone();
         event_map
             .entry(data.timestamp)
             .and_modify(|coins| {
                 coins
                     .entry(data.mint.clone())
                     .and_modify(|events| {
-                        events.push(event.clone());
+                        // 同一笔交易的同一个事件可能重复推送
+                        if !events.iter().any(|e| e.context == event.context) {
+                            events.push(event.clone());
+                        }
                     })
                     .or_insert(vec![event.clone()]);
             })
@@ -159,8 +165,7 @@ impl Statistics {
 }
 
 impl MonitorRule {
-    pub async fn deal_scan_dealer(&self, event: &TradeEvent) -> Result<()> {
-        let event = event.clone();
+    pub async fn deal_scan_dealer(&self, event: TxEvent<TradeEvent>) -> Result<()> {
         tokio::spawn(async move {
             let statistics = get_global_statistics_manager().await.clone();
             statistics.add_event(&event).await;
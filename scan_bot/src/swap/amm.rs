@@ -0,0 +1,215 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signer::Signer,
+    system_instruction, system_program,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::instruction::{close_account, sync_native};
+use tracing::{debug, info};
+
+use super::Swap;
+use crate::sol_client::{
+    pump_amm::{
+        get_coin_creator_vault_authority, AmmPool, PUMP_AMM_EVENT_AUTHORITY,
+        PUMP_AMM_GLOBAL_CONFIG, PUMP_AMM_PROGRAM, WSOL_MINT,
+    },
+    ASSOCIATED_TOKEN_PROGRAM, PUMP_BUY_METHOD, PUMP_SELL_METHOD,
+};
+
+impl Swap {
+    /// Swap instructions against the pump swap pool of a graduated token.
+    ///
+    /// Same arguments as the bonding curve swap: buy `token_amount` paying at
+    /// most `sol_amount`, or sell `token_amount` for at least `sol_amount`.
+    /// Sol is wrapped into a temporary wsol account which is closed afterwards.
+    pub fn amm_swap_instructions(
+        &self,
+        is_buy: bool,
+        pool: &AmmPool,
+        token_amount: u64,
+        sol_amount: u64,
+    ) -> Result<Vec<Instruction>> {
+        if token_amount == 0 {
+            return Err(anyhow!("No instructions to execute"));
+        }
+
+        let owner = self.keypair.pubkey();
+        let token_program = spl_token::ID;
+        let mint = pool.state.base_mint;
+        let wsol = Pubkey::from_str_const(WSOL_MINT);
+        if pool.state.quote_mint != wsol {
+            return Err(anyhow!(
+                "amm pool {} is not quoted in wsol: {}",
+                pool.address,
+                pool.state.quote_mint
+            ));
+        }
+
+        let mint_ata = get_associated_token_address(&owner, &mint);
+        let wsol_ata = get_associated_token_address(&owner, &wsol);
+        let protocol_fee_recipient = pool.global_config.protocol_fee_recipient()?;
+        let protocol_fee_recipient_ata =
+            get_associated_token_address(&protocol_fee_recipient, &wsol);
+        let coin_creator_vault_authority =
+            get_coin_creator_vault_authority(&pool.state.coin_creator);
+        let coin_creator_vault_ata =
+            get_associated_token_address(&coin_creator_vault_authority, &wsol);
+        let amm_program = Pubkey::from_str_const(PUMP_AMM_PROGRAM);
+
+        debug!(
+            "amm swap: pool: {}, mint: {}, mint_ata: {}, wsol_ata: {}",
+            pool.address, mint, mint_ata, wsol_ata
+        );
+
+        let accounts = vec![
+            AccountMeta::new_readonly(pool.address, false),
+            AccountMeta::new(owner, true),
+            AccountMeta::new_readonly(Pubkey::from_str_const(PUMP_AMM_GLOBAL_CONFIG), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(wsol, false),
+            AccountMeta::new(mint_ata, false),
+            AccountMeta::new(wsol_ata, false),
+            AccountMeta::new(pool.state.pool_base_token_account, false),
+            AccountMeta::new(pool.state.pool_quote_token_account, false),
+            AccountMeta::new_readonly(protocol_fee_recipient, false),
+            AccountMeta::new(protocol_fee_recipient_ata, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(Pubkey::from_str_const(ASSOCIATED_TOKEN_PROGRAM), false),
+            AccountMeta::new_readonly(Pubkey::from_str_const(PUMP_AMM_EVENT_AUTHORITY), false),
+            AccountMeta::new_readonly(amm_program, false),
+            AccountMeta::new(coin_creator_vault_ata, false),
+            AccountMeta::new_readonly(coin_creator_vault_authority, false),
+        ];
+
+        info!(
+            "amm swap: is_buy: {}, pool: {}, token_amount: {}, sol_amount_threshold: {}",
+            is_buy, pool.address, token_amount, sol_amount
+        );
+
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &owner,
+            &owner,
+            &wsol,
+            &token_program,
+        )];
+        if is_buy {
+            // wrap the max cost, what's left is unwrapped by closing the account
+            instructions.push(system_instruction::transfer(&owner, &wsol_ata, sol_amount));
+            instructions.push(sync_native(&token_program, &wsol_ata)?);
+            instructions.push(create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &mint,
+                &token_program,
+            ));
+            instructions.push(Instruction::new_with_bincode(
+                amm_program,
+                &(PUMP_BUY_METHOD, token_amount, sol_amount),
+                accounts,
+            ));
+        } else {
+            instructions.push(Instruction::new_with_bincode(
+                amm_program,
+                &(PUMP_SELL_METHOD, token_amount, sol_amount),
+                accounts,
+            ));
+        }
+        instructions.push(close_account(
+            &token_program,
+            &wsol_ata,
+            &owner,
+            &owner,
+            &[],
+        )?);
+
+        Ok(instructions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use solana_sdk::signature::Keypair;
+
+    use super::*;
+    use crate::sol_client::pump_amm::{
+        GlobalConfig, PoolState, GLOBAL_CONFIG_ACCOUNT_DISCRIMINATOR, POOL_ACCOUNT_DISCRIMINATOR,
+    };
+
+    fn pool() -> AmmPool {
+        let mut protocol_fee_recipients = [Pubkey::default(); 8];
+        protocol_fee_recipients[0] = Pubkey::new_unique();
+        AmmPool {
+            address: Pubkey::new_unique(),
+            state: PoolState {
+                discriminator: POOL_ACCOUNT_DISCRIMINATOR,
+                pool_bump: 255,
+                index: 0,
+                creator: Pubkey::new_unique(),
+                base_mint: Pubkey::new_unique(),
+                quote_mint: Pubkey::from_str_const(WSOL_MINT),
+                lp_mint: Pubkey::new_unique(),
+                pool_base_token_account: Pubkey::new_unique(),
+                pool_quote_token_account: Pubkey::new_unique(),
+                lp_supply: 0,
+                coin_creator: Pubkey::new_unique(),
+            },
+            global_config: GlobalConfig {
+                discriminator: GLOBAL_CONFIG_ACCOUNT_DISCRIMINATOR,
+                admin: Pubkey::new_unique(),
+                lp_fee_basis_points: 20,
+                protocol_fee_basis_points: 5,
+                disable_flags: 0,
+                protocol_fee_recipients,
+                coin_creator_fee_basis_points: 5,
+            },
+            base_reserves: 200_000_000_000_000,
+            quote_reserves: 80_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_amm_swap_instructions() -> Result<()> {
+        let swap = Swap::new(Arc::new(Keypair::new()));
+        let pool = pool();
+        let amm_program = Pubkey::from_str_const(PUMP_AMM_PROGRAM);
+
+        let buy = swap.amm_swap_instructions(true, &pool, 1_000_000, 2_000_000)?;
+        assert_eq!(buy.len(), 6);
+        let buy_ix = &buy[4];
+        assert_eq!(buy_ix.program_id, amm_program);
+        assert_eq!(buy_ix.accounts.len(), 19);
+        assert_eq!(&buy_ix.data[..8], &PUMP_BUY_METHOD.to_le_bytes());
+        assert_eq!(&buy_ix.data[8..16], &1_000_000u64.to_le_bytes());
+        assert_eq!(&buy_ix.data[16..24], &2_000_000u64.to_le_bytes());
+
+        let sell = swap.amm_swap_instructions(false, &pool, 1_000_000, 2_000_000)?;
+        assert_eq!(sell.len(), 3);
+        assert_eq!(&sell[1].data[..8], &PUMP_SELL_METHOD.to_le_bytes());
+
+        assert!(swap
+            .amm_swap_instructions(true, &pool, 0, 2_000_000)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_amm_quote() -> Result<()> {
+        let pool = pool();
+        let fee_basis_points = pool.global_config.total_fee_basis_points();
+        assert_eq!(fee_basis_points, 30);
+
+        let quote = pool.reserves().quote_buy(1_003_000_000, fee_basis_points)?;
+        assert_eq!(quote.fee, 3_000_000);
+        // 1 sol into 80 sol: 1e9 * 2e14 / 81e9
+        assert_eq!(quote.amount_out, 2_469_135_802_469);
+        Ok(())
+    }
+}
@@ -9,22 +9,134 @@ use solana_sdk::{
     system_program,
 };
 use spl_associated_token_account::{
-    get_associated_token_address, instruction::create_associated_token_account,
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
 };
+use spl_token::instruction::close_account;
 use tracing::{debug, info};
 
-use crate::sol_client::{
-    client::SolanaMonitor, ASSOCIATED_TOKEN_PROGRAM, PUMP_ACCOUNT, PUMP_BUY_METHOD,
-    PUMP_FEE_RECIPIENT, PUMP_GLOBAL, PUMP_PROGRAM, PUMP_SELL_METHOD, RENT_PROGRAM,
+pub mod amm;
+
+use crate::{
+    config::get_global_config,
+    jito::tip_policy::{TipRequest, Urgency},
+    sol_client::{
+        client::SolanaMonitor,
+        landing::SendResult,
+        pump_amm::AmmPool,
+        quote::{CurveReserves, PUMP_FEE_BASIS_POINTS},
+        BondingCurveAccount, ASSOCIATED_TOKEN_PROGRAM, PUMP_ACCOUNT, PUMP_BUY_METHOD,
+        PUMP_FEE_RECIPIENT, PUMP_GLOBAL, PUMP_PROGRAM, PUMP_SELL_METHOD, RENT_PROGRAM,
+    },
 };
 
+// amount * (100 + slippage) / 100, slippage in percent
+pub fn with_slippage_up(amount: u64, slippage: u64) -> Result<u64> {
+    let v = (amount as u128) * (100 + slippage as u128) / 100;
+    u64::try_from(v).map_err(|_| anyhow!("slippage: amount overflows u64: {}", v))
+}
+
+// amount * (100 - slippage) / 100, slippage in percent
+pub fn with_slippage_down(amount: u64, slippage: u64) -> u64 {
+    ((amount as u128) * (100 - slippage.min(100) as u128) / 100) as u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SellAmount {
+    Amount(u64),                 // exact token amount
+    Percent(u64),                // percent of the balance, 1-100
+    All { close_account: bool }, // whole balance, optionally close the ata
+}
+
+impl SellAmount {
+    pub fn token_amount(&self, balance: u64) -> Result<u64> {
+        match *self {
+            SellAmount::Amount(amount) => {
+                if amount > balance {
+                    return Err(anyhow!(
+                        "sell amount {} exceeds balance {}",
+                        amount,
+                        balance
+                    ));
+                }
+                Ok(amount)
+            }
+            SellAmount::Percent(percent) => {
+                if percent == 0 || percent > 100 {
+                    return Err(anyhow!("invalid sell percent: {}", percent));
+                }
+                Ok(((balance as u128) * (percent as u128) / 100) as u64)
+            }
+            SellAmount::All { .. } => Ok(balance),
+        }
+    }
+}
+
+/// Where a token trades: its bonding curve, or the amm pool once graduated.
+pub enum SwapRoute {
+    BondingCurve(BondingCurveAccount),
+    Amm(Box<AmmPool>),
+}
+
+impl SwapRoute {
+    pub async fn resolve(client: &SolanaMonitor, address: &str) -> Result<Self> {
+        let (_, bonding_curve_account) = client.get_bonding_curve_account(address).await?;
+        if !bonding_curve_account.complete {
+            return Ok(SwapRoute::BondingCurve(bonding_curve_account));
+        }
+
+        info!("bonding curve of {} is complete, route to amm", address);
+        let pool = client.get_amm_pool(address).await?;
+        Ok(SwapRoute::Amm(Box::new(pool)))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SwapRoute::BondingCurve(_) => "bonding_curve",
+            SwapRoute::Amm(_) => "amm",
+        }
+    }
+
+    pub fn reserves(&self) -> CurveReserves {
+        match self {
+            SwapRoute::BondingCurve(account) => account.reserves(),
+            SwapRoute::Amm(pool) => pool.reserves(),
+        }
+    }
+
+    pub fn fee_basis_points(&self) -> u64 {
+        match self {
+            SwapRoute::BondingCurve(_) => PUMP_FEE_BASIS_POINTS,
+            SwapRoute::Amm(pool) => pool.global_config.total_fee_basis_points(),
+        }
+    }
+}
+
 pub struct Swap {
     pub keypair: Arc<Keypair>,
+    pub urgency: Urgency, // scales the jito tip of every trade
 }
 
 impl Swap {
     pub fn new(keypair: Arc<Keypair>) -> Self {
-        Self { keypair }
+        Self {
+            keypair,
+            urgency: Urgency::default(),
+        }
+    }
+
+    /// Same wallet, trading at `urgency`.
+    pub fn with_urgency(&self, urgency: Urgency) -> Self {
+        Self {
+            keypair: self.keypair.clone(),
+            urgency,
+        }
+    }
+
+    fn tip_request(&self, trade_lamports: u64) -> TipRequest {
+        TipRequest {
+            trade_lamports,
+            urgency: self.urgency,
+        }
     }
 
     pub async fn swap(
@@ -34,7 +146,67 @@ impl Swap {
         token_amount: u64,       // token amount
         sol_amount: u64,         // sol amount
         buy_again: Option<bool>, // buy again
-    ) -> Result<Vec<String>> {
+    ) -> Result<SendResult> {
+        let client = SolanaMonitor::default_client().await;
+        let (instructions, _) = tokio::try_join!(
+            self.swap_instructions(is_buy, address, token_amount, sol_amount, buy_again),
+            client.prefetch_send_state(),
+        )?;
+
+        client
+            .new_signed_and_send(&self.keypair, instructions, self.tip_request(sol_amount))
+            .await
+    }
+
+    /// Swap instructions for `address`, against the bonding curve or, once the
+    /// curve is complete, against the amm pool it migrated to.
+    pub async fn swap_instructions(
+        &self,
+        is_buy: bool,
+        address: &str,
+        token_amount: u64,       // token amount
+        sol_amount: u64,         // sol amount
+        buy_again: Option<bool>, // buy again
+    ) -> Result<Vec<Instruction>> {
+        let client = SolanaMonitor::default_client().await;
+        match SwapRoute::resolve(&client, address).await? {
+            SwapRoute::BondingCurve(_) => {
+                self.pump_swap_instructions(is_buy, address, token_amount, sol_amount, buy_again)
+                    .await
+            }
+            SwapRoute::Amm(pool) => {
+                self.amm_swap_instructions(is_buy, &pool, token_amount, sol_amount)
+            }
+        }
+    }
+
+    async fn route_swap_instructions(
+        &self,
+        route: &SwapRoute,
+        is_buy: bool,
+        address: &str,
+        token_amount: u64,
+        sol_amount: u64,
+    ) -> Result<Vec<Instruction>> {
+        match route {
+            SwapRoute::BondingCurve(_) => {
+                self.pump_swap_instructions(is_buy, address, token_amount, sol_amount, None)
+                    .await
+            }
+            SwapRoute::Amm(pool) => {
+                self.amm_swap_instructions(is_buy, pool, token_amount, sol_amount)
+            }
+        }
+    }
+
+    async fn pump_swap_instructions(
+        &self,
+        is_buy: bool,
+        address: &str,
+        token_amount: u64,       // token amount
+        sol_amount: u64,         // sol amount
+        buy_again: Option<bool>, // buy again
+    ) -> Result<Vec<Instruction>> {
         let owner = self.keypair.pubkey();
         let mint =
             Pubkey::from_str(address).map_err(|e| anyhow!("failed to parse mint pubkey: {}", e))?;
@@ -61,16 +233,14 @@ impl Swap {
         );
 
         let mut create_instruction = None;
-        if is_buy {
-            if is_buy && !buy_again {
-                debug!("Creating associated token account for mint {}", mint);
-                create_instruction = Some(create_associated_token_account(
-                    &owner,
-                    &owner,
-                    &mint,
-                    &program_id,
-                ));
-            }
+        if is_buy && !buy_again {
+            debug!("Creating associated token account for mint {}", mint);
+            create_instruction = Some(create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &mint,
+                &program_id,
+            ));
         }
 
         let pump_program = Pubkey::from_str_const(PUMP_PROGRAM);
@@ -141,11 +311,122 @@ impl Swap {
 
         debug!("instructions: {:?}", instructions);
 
-        // tx::new_signed_and_send().await
+        Ok(instructions)
+    }
+
+    /// Buy `address` with `sol_in` lamports (fee included).
+    ///
+    /// Token amount is quoted from the live bonding curve, max sol cost is
+    /// `sol_in` plus the configured slippage.
+    pub async fn buy_with_sol(&self, address: &str, sol_in: u64) -> Result<SendResult> {
+        let c = get_global_config().await;
+        let client = SolanaMonitor::default_client().await;
+        let (route, _) = tokio::try_join!(
+            SwapRoute::resolve(&client, address),
+            client.prefetch_send_state(),
+        )?;
+
+        let quote = route
+            .reserves()
+            .quote_buy(sol_in, route.fee_basis_points())?;
+        let max_sol_cost = with_slippage_up(sol_in, c.jito_config.slippage)?;
+        info!(
+            "buy_with_sol: address: {}, route: {}, sol_in: {}, token_out: {}, max_sol_cost: {}, price_impact: {:.4}",
+            address, route.name(), sol_in, quote.amount_out, max_sol_cost, quote.price_impact
+        );
+
+        let instructions = self
+            .route_swap_instructions(&route, true, address, quote.amount_out, max_sol_cost)
+            .await?;
         client
-            .new_signed_and_send(&self.keypair, instructions)
+            .new_signed_and_send(&self.keypair, instructions, self.tip_request(sol_in))
+            .await
+    }
+
+    /// Sell `token_amount` of `address`.
+    ///
+    /// Min sol output is the quoted output minus the configured slippage.
+    pub async fn sell_tokens(&self, address: &str, token_amount: u64) -> Result<SendResult> {
+        self.sell_tokens_and_close(address, token_amount, false)
+            .await
+    }
+
+    /// Sell part or all of the wallet's balance of `address`.
+    pub async fn sell(&self, address: &str, amount: SellAmount) -> Result<SendResult> {
+        let owner = self.keypair.pubkey();
+        let mint =
+            Pubkey::from_str(address).map_err(|e| anyhow!("failed to parse mint pubkey: {}", e))?;
+
+        let client = SolanaMonitor::default_client().await;
+        let balance = client.get_token_balance(&mint, &owner).await?;
+        let token_amount = amount.token_amount(balance)?;
+        if token_amount == 0 {
+            return Err(anyhow!("no {} to sell, balance: {}", address, balance));
+        }
+
+        let close_account = matches!(
+            amount,
+            SellAmount::All {
+                close_account: true
+            }
+        );
+        self.sell_tokens_and_close(address, token_amount, close_account)
             .await
     }
+
+    async fn sell_tokens_and_close(
+        &self,
+        address: &str,
+        token_amount: u64,
+        close_account: bool,
+    ) -> Result<SendResult> {
+        let c = get_global_config().await;
+        let client = SolanaMonitor::default_client().await;
+        let (route, _) = tokio::try_join!(
+            SwapRoute::resolve(&client, address),
+            client.prefetch_send_state(),
+        )?;
+
+        let quote = route
+            .reserves()
+            .quote_sell(token_amount, route.fee_basis_points())?;
+        let min_sol_output = with_slippage_down(quote.amount_out, c.jito_config.slippage);
+        info!(
+            "sell_tokens: address: {}, route: {}, token_in: {}, sol_out: {}, min_sol_output: {}, price_impact: {:.4}, close_account: {}",
+            address, route.name(), token_amount, quote.amount_out, min_sol_output, quote.price_impact, close_account
+        );
+
+        let mut instructions = self
+            .route_swap_instructions(&route, false, address, token_amount, min_sol_output)
+            .await?;
+        if close_account {
+            instructions.push(self.close_account_instruction(address)?);
+        }
+
+        client
+            .new_signed_and_send(
+                &self.keypair,
+                instructions,
+                self.tip_request(quote.amount_out),
+            )
+            .await
+    }
+
+    // close the owner's emptied ata of `address`, rent goes back to the owner
+    fn close_account_instruction(&self, address: &str) -> Result<Instruction> {
+        let owner = self.keypair.pubkey();
+        let mint =
+            Pubkey::from_str(address).map_err(|e| anyhow!("failed to parse mint pubkey: {}", e))?;
+        let mint_ata = get_associated_token_address(&owner, &mint);
+
+        Ok(close_account(
+            &spl_token::ID,
+            &mint_ata,
+            &owner,
+            &owner,
+            &[],
+        )?)
+    }
 }
 
 #[cfg(test)]
@@ -171,4 +452,33 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_sell_amount() -> Result<()> {
+        assert_eq!(SellAmount::Amount(100).token_amount(1_000)?, 100);
+        assert!(SellAmount::Amount(1_001).token_amount(1_000).is_err());
+
+        assert_eq!(SellAmount::Percent(50).token_amount(1_001)?, 500);
+        assert_eq!(SellAmount::Percent(100).token_amount(1_001)?, 1_001);
+        assert!(SellAmount::Percent(0).token_amount(1_000).is_err());
+        assert!(SellAmount::Percent(101).token_amount(1_000).is_err());
+
+        let all = SellAmount::All {
+            close_account: true,
+        };
+        assert_eq!(all.token_amount(1_000)?, 1_000);
+        assert_eq!(all.token_amount(0)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_slippage() -> Result<()> {
+        assert_eq!(with_slippage_up(1_000_000_000, 28)?, 1_280_000_000);
+        assert_eq!(with_slippage_up(1_000_000_000, 0)?, 1_000_000_000);
+        assert!(with_slippage_up(u64::MAX, 1).is_err());
+
+        assert_eq!(with_slippage_down(1_000_000_000, 28), 720_000_000);
+        assert_eq!(with_slippage_down(1_000_000_000, 150), 0);
+        Ok(())
+    }
 }